@@ -0,0 +1,50 @@
+use clap::Args;
+
+use crate::{
+    Failure, Output, ResultCode, ToolResult,
+    commands::start::{actions::cell_from_number, analysis::print_analysis},
+    services::{config::AppConfig, db::records::ToolsAnalytics, helper::HelperService},
+};
+
+#[derive(Debug, Args)]
+#[command(about = ABOUT_ANALYZE, long_about = LONG_ABOUT_ANALYZE)]
+pub struct ArgsAnalyze {
+    #[arg(
+        value_delimiter = ',',
+        num_args = 1..,
+        required = true,
+        help = "Moves in order, X first, as cell numbers 1-9 counted row by row (e.g. 5,1,9)"
+    )]
+    moves: Vec<u16>,
+}
+
+const ABOUT_ANALYZE: &'static str = "Analyze a game";
+const LONG_ABOUT_ANALYZE: &'static str =
+    "Replay a game given as a move list and grade every move against the engine.";
+
+pub fn handle(args: ArgsAnalyze, _cfg: &mut AppConfig, helper: &HelperService) -> ToolResult {
+    let mut moves: Vec<(usize, usize)> = vec![];
+    for cell in args.moves.iter() {
+        if *cell < 1 || *cell > 9 {
+            return Err(Failure {
+                message: "Invalid move list".to_string(),
+                trace: format!("Reason: Cell {} is out of range, use 1-9", cell),
+                code: ResultCode::InvalidArgs,
+            });
+        }
+        moves.push(cell_from_number(*cell));
+    }
+
+    print_analysis(&moves, true)?;
+
+    let analytics = helper.update_command_usage(ToolsAnalytics::ANALYZE);
+
+    Ok(Output {
+        message: None,
+        code: if analytics.is_ok() {
+            ResultCode::Success
+        } else {
+            ResultCode::SuccessAnalyticsFailed
+        },
+    })
+}
@@ -0,0 +1,242 @@
+use std::time::{Duration, Instant};
+
+use clap::Args;
+use comfy_table::{Cell, Table};
+use indicatif::ProgressBar;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    AppResult, Output, ResultCode, ToolResult,
+    commands::start::{
+        actions::{check_win_slim, get_selectable},
+        book::OpeningBook,
+        computer::{Algorithm, EngineSettings, make_move, nodes_searched},
+        external::ExternalEngine,
+        game::{GameMatrixWrapper, Player},
+        oracle::oracle,
+    },
+    services::{
+        config::AppConfig,
+        db::records::ToolsAnalytics,
+        helper::{HelperService, parse_duration},
+    },
+};
+
+#[derive(Debug, Args)]
+#[command(about = ABOUT_ARENA, long_about = LONG_ABOUT_ARENA)]
+pub struct ArgsArena {
+    #[arg(short, long, default_value = "100", help = "Number of games to play")]
+    games: u32,
+    #[arg(long, default_value = "3", value_parser = clap::value_parser!(u8).range(1..=4), help = "Difficulty of engine A; 1 = Easy, 2 = Normal, 3 = Hard, 4 = Perfect")]
+    a_difficulty: u8,
+    #[arg(long, default_value = "minimax", help = "Search algorithm of engine A")]
+    a_algorithm: Algorithm,
+    #[arg(long, help = "Seed for engine A; derived from --seed when not set")]
+    a_seed: Option<u64>,
+    #[arg(long, help = "Use an external program as engine A (see \"start --engine-cmd\")")]
+    a_cmd: Option<String>,
+    #[arg(long, default_value = "3", value_parser = clap::value_parser!(u8).range(1..=4), help = "Difficulty of engine B")]
+    b_difficulty: u8,
+    #[arg(long, default_value = "minimax", help = "Search algorithm of engine B")]
+    b_algorithm: Algorithm,
+    #[arg(long, help = "Seed for engine B; derived from --seed when not set")]
+    b_seed: Option<u64>,
+    #[arg(long, help = "Use an external program as engine B")]
+    b_cmd: Option<String>,
+    #[arg(long, default_value = "5s", value_parser = parse_duration, help = "How long external engines may take to answer")]
+    engine_timeout: Duration,
+    #[arg(long, value_parser = parse_duration, help = "Time both engines may think per move with mcts or deepening")]
+    think_time: Option<Duration>,
+    #[arg(long, action = clap::ArgAction::SetTrue, help = "Don't let the engines play from the opening book")]
+    no_book: bool,
+}
+
+const ABOUT_ARENA: &'static str = "Play two engines against each other";
+const LONG_ABOUT_ARENA: &'static str = "Play a match between two engines, swapping sides every game, and report results, average move time and search effort per move: positions examined by minimax and deepening, playouts by mcts.";
+
+struct Contender {
+    name: String,
+    engine: EngineSettings,
+    external: Option<ExternalEngine>,
+    rng: ChaCha8Rng,
+    wins: u32,
+    draws: u32,
+    losses: u32,
+    moves: u32,
+    time: Duration,
+    nodes: u64,
+}
+
+impl Contender {
+    fn new(
+        label: &str,
+        engine: EngineSettings,
+        external: Option<ExternalEngine>,
+        seed: u64,
+    ) -> Self {
+        Contender {
+            name: match &external {
+                Some(g) => format!("{}: {}", label, g.name),
+                None => format!("{}: {} (difficulty {})", label, engine.algorithm, engine.difficulty),
+            },
+            engine,
+            external,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            moves: 0,
+            time: Duration::ZERO,
+            nodes: 0,
+        }
+    }
+
+    fn row(&self) -> Vec<Cell> {
+        let moves = self.moves.max(1);
+        let games = (self.wins + self.draws + self.losses).max(1);
+        vec![
+            Cell::new(&self.name),
+            Cell::new(self.wins),
+            Cell::new(self.draws),
+            Cell::new(self.losses),
+            Cell::new(format!(
+                "{:.1}%",
+                (self.wins as f64 + self.draws as f64 / 2.0) * 100.0 / games as f64
+            )),
+            Cell::new(format!("{:.3}ms", self.time.as_secs_f64() * 1000.0 / moves as f64)),
+            Cell::new(self.search(moves)),
+        ]
+    }
+
+    /// Average search effort per move in the unit the algorithm counts.
+    fn search(&self, moves: u32) -> String {
+        let average = self.nodes / moves as u64;
+        match (&self.external, self.engine.algorithm) {
+            (Some(_), _) | (None, Algorithm::Menace) => "-".to_string(),
+            (None, Algorithm::Mcts) => format!("{} playouts", average),
+            (None, _) => format!("{} positions", average),
+        }
+    }
+}
+
+fn spawn(command: &Option<String>, timeout: Duration) -> AppResult<Option<ExternalEngine>> {
+    match command {
+        Some(command) => Ok(Some(ExternalEngine::spawn(command, timeout)?)),
+        None => Ok(None),
+    }
+}
+
+pub fn handle(args: ArgsArena, _cfg: &mut AppConfig, helper: &HelperService) -> ToolResult {
+    let book = if args.no_book { None } else { Some(OpeningBook::load()?) };
+    let menace = if args.a_algorithm == Algorithm::Menace || args.b_algorithm == Algorithm::Menace {
+        Some(helper.get_menace()?)
+    } else {
+        None
+    };
+    let engine = |difficulty: u8, algorithm: Algorithm| EngineSettings {
+        difficulty,
+        algorithm,
+        book: book.clone(),
+        think_time: args.think_time,
+        menace: menace.clone(),
+        ..EngineSettings::default()
+    };
+    let (seed_a, seed_b) = helper.rng.with(|rng| (rng.random::<u64>(), rng.random::<u64>()));
+
+    let mut contenders = [
+        Contender::new(
+            "A",
+            engine(args.a_difficulty, args.a_algorithm),
+            spawn(&args.a_cmd, args.engine_timeout)?,
+            args.a_seed.unwrap_or(seed_a),
+        ),
+        Contender::new(
+            "B",
+            engine(args.b_difficulty, args.b_algorithm),
+            spawn(&args.b_cmd, args.engine_timeout)?,
+            args.b_seed.unwrap_or(seed_b),
+        ),
+    ];
+
+    // Solve the game up front so the first move isn't charged for it
+    oracle();
+
+    let progress = ProgressBar::new(args.games as u64);
+    for n in 0..args.games {
+        // Engine A plays X in even games, B in odd ones
+        let first = (n % 2) as usize;
+        let mut game_matrix = GameMatrixWrapper::default();
+        let mut player = Player::X;
+        let mut forfeit: Option<Player> = None;
+
+        while check_win_slim(&game_matrix.value()).is_none()
+            && get_selectable(&game_matrix.value()) > 0
+        {
+            let side = if player == Player::X { first } else { 1 - first };
+            let contender = &mut contenders[side];
+
+            let nodes = nodes_searched();
+            let started = Instant::now();
+            match &contender.external {
+                Some(engine) => match engine.request_move(&game_matrix.value(), player) {
+                    Ok(cell) => game_matrix.set_position(cell, player),
+                    Err(reason) => {
+                        progress.println(format!("{} forfeits game {}: {}", engine.name, n + 1, reason));
+                        forfeit = Some(player);
+                        break;
+                    }
+                },
+                None => {
+                    make_move(&mut game_matrix, player, &contender.engine, &mut contender.rng);
+                }
+            }
+            contender.time += started.elapsed();
+            contender.nodes += nodes_searched() - nodes;
+            contender.moves += 1;
+
+            player = player.invert();
+        }
+
+        let winner = match forfeit {
+            Some(g) => Some(g.invert()),
+            None => check_win_slim(&game_matrix.value()).and_then(Player::from_i32),
+        };
+        for contender in contenders.iter() {
+            if let Some(engine) = &contender.external {
+                engine.send_result(winner);
+            }
+        }
+        match winner {
+            Some(winner) => {
+                let side = if winner == Player::X { first } else { 1 - first };
+                contenders[side].wins += 1;
+                contenders[1 - side].losses += 1;
+            }
+            None => contenders.iter_mut().for_each(|g| g.draws += 1),
+        }
+        progress.inc(1);
+    }
+    progress.finish_and_clear();
+
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Engine", "Wins", "Draws", "Losses", "Score", "Avg Move Time", "Avg Search",
+    ]);
+    for contender in contenders.iter() {
+        table.add_row(contender.row());
+    }
+    println!("{}", table);
+    println!("Seed: {}", helper.rng.seed());
+
+    let analytics = helper.update_command_usage(ToolsAnalytics::ARENA);
+
+    Ok(Output {
+        message: None,
+        code: if analytics.is_ok() {
+            ResultCode::Success
+        } else {
+            ResultCode::SuccessAnalyticsFailed
+        },
+    })
+}
@@ -0,0 +1,122 @@
+use std::{cmp::Reverse, fs};
+
+use clap::{Args, Subcommand};
+use comfy_table::{Cell, Table};
+use indicatif::ProgressBar;
+
+use crate::{
+    Failure, Output, ResultCode, ToolResult,
+    commands::start::{
+        actions::{cell_number, check_win_slim, get_selectable},
+        book::OpeningBook,
+        computer::{EngineSettings, make_move},
+        game::{GameMatrixWrapper, Player},
+    },
+    services::{config::AppConfig, db::records::ToolsAnalytics, helper::HelperService},
+};
+
+#[derive(Debug, Args)]
+#[command(about = ABOUT_BOOK, long_about = LONG_ABOUT_BOOK)]
+pub struct ArgsBook {
+    #[command(subcommand)]
+    action: BookAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum BookAction {
+    #[command(about = "Add entries to your book from engine self-play")]
+    Build {
+        #[arg(short, long, default_value = "200", help = "Number of self-play games")]
+        games: u32,
+        #[arg(short, long, default_value = "4", help = "Record moves up to this many plies into the game")]
+        plies: usize,
+    },
+    #[command(about = "Show the book in use, built-in entries included")]
+    Show,
+    #[command(about = "Delete your book file, keeping the built-in entries")]
+    Clear,
+}
+
+const ABOUT_BOOK: &'static str = "Manage the opening book";
+const LONG_ABOUT_BOOK: &'static str = "Manage the opening book the computer plays from. Your entries are stored in an editable JSON file and take precedence over the built-in ones.";
+
+pub fn handle(args: ArgsBook, _cfg: &mut AppConfig, helper: &HelperService) -> ToolResult {
+    let message = match args.action {
+        BookAction::Build { games, plies } => {
+            let mut book = OpeningBook::load_user()?;
+            let engine = EngineSettings::default();
+            let progress = ProgressBar::new(games as u64);
+
+            for _ in 0..games {
+                let mut game_matrix = GameMatrixWrapper::default();
+                let mut player = Player::X;
+                let mut ply = 0;
+
+                while ply < plies
+                    && check_win_slim(&game_matrix.value()).is_none()
+                    && get_selectable(&game_matrix.value()) > 0
+                {
+                    let before = game_matrix.value();
+                    let cell =
+                        helper.rng.with(|rng| make_move(&mut game_matrix, player, &engine, rng));
+                    book.add(&before, cell_number(cell), 1);
+                    player = player.invert();
+                    ply += 1;
+                }
+                progress.inc(1);
+            }
+            progress.finish_and_clear();
+
+            book.save_user()?;
+            format!(
+                "Book now has {} position(s), saved to {}",
+                book.entries.len(),
+                OpeningBook::path()?.display()
+            )
+        }
+        BookAction::Show => {
+            let book = OpeningBook::load()?;
+            let mut entries: Vec<_> = book.entries.iter().collect();
+            entries.sort_by_key(|g| (Reverse(g.0.matches('.').count()), g.0.clone()));
+
+            let mut table = Table::new();
+            table.set_header(vec!["Position", "Moves (cell:weight)"]);
+            for (board, moves) in entries {
+                table.add_row(vec![
+                    Cell::new(board),
+                    Cell::new(
+                        moves
+                            .iter()
+                            .map(|g| format!("{}:{}", g.cell, g.weight))
+                            .collect::<Vec<String>>()
+                            .join(" "),
+                    ),
+                ]);
+            }
+            println!("{}", table);
+            format!("Your entries are stored in {}", OpeningBook::path()?.display())
+        }
+        BookAction::Clear => {
+            let path = OpeningBook::path()?;
+            if path.exists() {
+                fs::remove_file(&path).map_err(|e| Failure {
+                    message: "Failed to delete opening book".to_string(),
+                    trace: format!("Reason: {}", e),
+                    code: ResultCode::PathError,
+                })?;
+            }
+            "Opening book cleared".to_string()
+        }
+    };
+
+    let analytics = helper.update_command_usage(ToolsAnalytics::BOOK);
+
+    Ok(Output {
+        message: Some(message),
+        code: if analytics.is_ok() {
+            ResultCode::Success
+        } else {
+            ResultCode::SuccessAnalyticsFailed
+        },
+    })
+}
@@ -0,0 +1,172 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::Args;
+use colored::Colorize;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    Failure, Output, ResultCode, ToolResult,
+    commands::puzzle::{puzzles::generate_puzzle, solve_puzzle},
+    services::{
+        config::AppConfig,
+        db::records::{DailyAttempt, ToolsAnalytics},
+        helper::HelperService,
+    },
+};
+
+#[derive(Debug, Args)]
+#[command(about = ABOUT_DAILY, long_about = LONG_ABOUT_DAILY)]
+pub struct ArgsDaily {
+    #[arg(short, long, action = clap::ArgAction::SetTrue, help = "Show your streak and today's result without playing")]
+    status: bool,
+}
+
+const ABOUT_DAILY: &'static str = "Play the daily challenge";
+const LONG_ABOUT_DAILY: &'static str = "Play today's challenge. Everyone gets the same puzzle and computer replies for the date, and only the first attempt of the day counts.";
+
+const DAILY_SEED_SALT: u64 = 0x7469_6374_6163_746f;
+const POINTS_PER_MOVE: i32 = 100;
+
+pub fn handle(args: ArgsDaily, cfg: &mut AppConfig, helper: &HelperService) -> ToolResult {
+    let day = today();
+    let date = date_string(day);
+    let profile = cfg.user.clone().unwrap_or_default();
+    let record = helper.get_daily_challenge(&profile)?;
+
+    if let Some(attempt) = record.attempt_on(day) {
+        println!(
+            "{} {}",
+            format!("Daily challenge for {}:", date).bold(),
+            match attempt.solved {
+                true => format!("solved for {} points", attempt.score).green(),
+                false => "not solved".red(),
+            }
+        );
+        println!("Current streak: {} day(s)", record.streak(day));
+        return Ok(Output {
+            message: None,
+            code: ResultCode::Success,
+        });
+    }
+
+    if args.status {
+        println!("{}", format!("Daily challenge for {} is waiting for you.", date).bold());
+        println!("Current streak: {} day(s)", record.streak(day - 1));
+        return Ok(Output {
+            message: None,
+            code: ResultCode::Success,
+        });
+    }
+
+    // A fixed algorithm, so every build hands out the same puzzle for a date
+    let mut rng = ChaCha8Rng::seed_from_u64(day as u64 ^ DAILY_SEED_SALT);
+    let win_in = rng.random_range(2..=3);
+    let mut puzzle = generate_puzzle(win_in, &mut rng).ok_or(Failure {
+        message: "Could not generate today's challenge".to_string(),
+        trace: "".to_string(),
+        code: ResultCode::InvalidArgs,
+    })?;
+    puzzle.id = format!("daily-{}", date);
+
+    // The attempt is stored up front so quitting half way still uses up the day
+    let mut attempt = DailyAttempt {
+        day,
+        date,
+        puzzle: puzzle.id.clone(),
+        solved: false,
+        score: 0,
+    };
+    helper.record_daily_attempt(&profile, attempt.clone())?;
+
+    let solved = solve_puzzle(&puzzle, helper)?;
+    attempt.solved = solved;
+    attempt.score = if solved { puzzle.win_in as i32 * POINTS_PER_MOVE } else { 0 };
+    let record = helper.record_daily_attempt(&profile, attempt)?;
+
+    println!("\n{} {} day(s)", "Current streak:".bold(), record.streak(day));
+
+    let analytics = helper.update_command_usage(ToolsAnalytics::DAILY);
+
+    Ok(Output {
+        message: if solved {
+            Some("Daily challenge solved!".to_string())
+        } else {
+            None
+        },
+        code: if analytics.is_ok() {
+            ResultCode::Success
+        } else {
+            ResultCode::SuccessAnalyticsFailed
+        },
+    })
+}
+
+/// Days since the Unix epoch in UTC, so the challenge changes at the same moment for everyone.
+pub fn today() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|g| g.as_secs() as i64 / 86_400)
+        .unwrap_or(0)
+}
+
+/// Formats days since the epoch as YYYY-MM-DD.
+pub fn date_string(day: i64) -> String {
+    // Civil-from-days conversion for the proleptic Gregorian calendar
+    let z = day + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::date_string;
+    use crate::services::db::records::{DailyAttempt, DailyChallengeRecord};
+
+    #[test]
+    fn test_date_string() {
+        assert_eq!(date_string(0), "1970-01-01");
+        assert_eq!(date_string(-1), "1969-12-31");
+        assert_eq!(date_string(10_956), "1999-12-31");
+        assert_eq!(date_string(10_957), "2000-01-01");
+        assert_eq!(date_string(11_016), "2000-02-29");
+        assert_eq!(date_string(19_782), "2024-02-29");
+        assert_eq!(date_string(19_783), "2024-03-01");
+    }
+
+    #[test]
+    fn test_streak() {
+        let attempt = |day: i64, solved: bool| DailyAttempt {
+            day,
+            date: date_string(day),
+            puzzle: format!("daily-{}", date_string(day)),
+            solved,
+            score: 0,
+        };
+        let record = DailyChallengeRecord {
+            attempts: vec![
+                attempt(10, true),
+                attempt(11, true),
+                attempt(13, true),
+                attempt(14, true),
+                attempt(15, false),
+                attempt(16, true),
+            ],
+        };
+
+        // Day 12 was missed, day 15 not solved
+        assert_eq!(record.streak(11), 2);
+        assert_eq!(record.streak(12), 0);
+        assert_eq!(record.streak(14), 2);
+        assert_eq!(record.streak(15), 0);
+        assert_eq!(record.streak(16), 1);
+        assert_eq!(DailyChallengeRecord::default().streak(16), 0);
+    }
+}
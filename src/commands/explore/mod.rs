@@ -0,0 +1,254 @@
+use std::collections::HashSet;
+
+use clap::Args;
+use comfy_table::{Cell, Table};
+
+use crate::{
+    Failure, Output, ResultCode, ToolResult,
+    commands::start::{
+        actions::{
+            LINES, ai_select, ai_unselect, cell_number, check_win_slim, get_selectable,
+            matrix_from_notation, player_to_move, print_selectable_game_matrix,
+        },
+        computer::{Outcome, score_moves},
+        game::{GameMatrix, GameMatrixWrapper, Player},
+        symmetry::{board_key, canonical_key},
+    },
+    services::{config::AppConfig, db::records::ToolsAnalytics, helper::HelperService},
+};
+
+#[derive(Debug, Args)]
+#[command(about = ABOUT_EXPLORE, long_about = LONG_ABOUT_EXPLORE)]
+pub struct ArgsExplore {
+    #[arg(default_value = ".........", help = "Position to explore as nine cells row by row, X, O or . (e.g. X...O....)")]
+    board: String,
+    #[arg(short, long, help = "Stop enumerating this many plies from the position")]
+    depth: Option<usize>,
+}
+
+const ABOUT_EXPLORE: &'static str = "Explore the game tree";
+const LONG_ABOUT_EXPLORE: &'static str = "Enumerate every game from a position and count the positions reached and the games won, lost and drawn at each depth, along with the value of every move.";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DepthStats {
+    pub nodes: u64,
+    pub x_wins: u64,
+    pub o_wins: u64,
+    pub draws: u64,
+}
+
+impl DepthStats {
+    fn add(&mut self, other: &DepthStats) {
+        self.nodes += other.nodes;
+        self.x_wins += other.x_wins;
+        self.o_wins += other.o_wins;
+        self.draws += other.draws;
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TreeStats {
+    /// Move sequences of each length from the root, and the games that end there.
+    pub depths: Vec<DepthStats>,
+    /// Distinct boards reached, the root included.
+    pub positions: HashSet<u32>,
+    pub canonical: HashSet<u32>,
+}
+
+impl TreeStats {
+    pub fn totals(&self) -> DepthStats {
+        let mut total = DepthStats::default();
+        self.depths.iter().for_each(|g| total.add(g));
+        total
+    }
+}
+
+/// Walks every line of play from the position, up to `max_depth` plies when given.
+pub fn explore(gm: &GameMatrix, player: Player, max_depth: Option<usize>) -> TreeStats {
+    let mut stats = TreeStats::default();
+    walk(&mut gm.clone(), player, 0, max_depth, &mut stats);
+    stats
+}
+
+fn walk(
+    gm: &mut GameMatrix,
+    player: Player,
+    depth: usize,
+    max_depth: Option<usize>,
+    stats: &mut TreeStats,
+) {
+    stats.positions.insert(board_key(gm));
+    stats.canonical.insert(canonical_key(gm));
+    if check_win_slim(gm).is_some() || max_depth.is_some_and(|g| depth >= g) {
+        return;
+    }
+
+    for i in 1..=get_selectable(gm) {
+        let Some(pos) = ai_select(gm, i, player) else {
+            continue;
+        };
+        if stats.depths.len() <= depth {
+            stats.depths.push(DepthStats::default());
+        }
+        let level = &mut stats.depths[depth];
+        level.nodes += 1;
+        match check_win_slim(gm).and_then(Player::from_i32) {
+            Some(Player::X) => level.x_wins += 1,
+            Some(Player::O) => level.o_wins += 1,
+            None if get_selectable(gm) == 0 => level.draws += 1,
+            None => {}
+        }
+
+        walk(gm, player.invert(), depth + 1, max_depth, stats);
+        ai_unselect(gm, pos);
+    }
+}
+
+/// Whether the board can come up in a game: X moves first and play stops at the first line,
+/// so only the side that just moved can have one.
+fn is_reachable(gm: &GameMatrix) -> bool {
+    let count = |player: Player| gm.iter().flatten().filter(|&&g| g == player.as_i32()).count();
+    let has_line = |player: Player| {
+        LINES
+            .iter()
+            .any(|line| line.iter().all(|&(row, col)| gm[row][col] == player.as_i32()))
+    };
+    let (x, o) = (count(Player::X), count(Player::O));
+    match (has_line(Player::X), has_line(Player::O)) {
+        (true, true) => false,
+        (true, false) => x == o + 1,
+        (false, true) => x == o,
+        (false, false) => x == o || x == o + 1,
+    }
+}
+
+pub fn handle(args: ArgsExplore, _cfg: &mut AppConfig, helper: &HelperService) -> ToolResult {
+    let gm = matrix_from_notation(&args.board)
+        .filter(is_reachable)
+        .ok_or(Failure {
+            message: format!("Invalid board \"{}\"", args.board),
+            trace: "Reason: Use nine cells of X, O or . that a game can reach, X moving first"
+                .to_string(),
+            code: ResultCode::InvalidArgs,
+        })?;
+    let player = player_to_move(&gm);
+
+    print_selectable_game_matrix(&GameMatrixWrapper(gm));
+    println!("\n{} to move", player);
+
+    let stats = explore(&gm, player, args.depth);
+
+    let mut table = Table::new();
+    table.set_header(vec!["Depth", "Nodes", "X Wins", "O Wins", "Draws"]);
+    for (depth, level) in stats.depths.iter().enumerate() {
+        table.add_row(vec![
+            Cell::new(depth + 1),
+            Cell::new(level.nodes),
+            Cell::new(level.x_wins),
+            Cell::new(level.o_wins),
+            Cell::new(level.draws),
+        ]);
+    }
+    let total = stats.totals();
+    table.add_row(vec![
+        Cell::new("Total"),
+        Cell::new(total.nodes),
+        Cell::new(total.x_wins),
+        Cell::new(total.o_wins),
+        Cell::new(total.draws),
+    ]);
+    println!("{}", table);
+    println!(
+        "Reachable positions: {} ({} up to symmetry)",
+        stats.positions.len(),
+        stats.canonical.len()
+    );
+
+    if check_win_slim(&gm).is_none() && get_selectable(&gm) > 0 {
+        let mut moves = Table::new();
+        moves.set_header(vec!["Cell", "Value", "Games", "X Wins", "O Wins", "Draws"]);
+        for (position, score) in score_moves(&gm, player) {
+            let mut child = gm;
+            let Some(cell) = ai_select(&mut child, position, player) else {
+                continue;
+            };
+            // The move itself, then everything below it
+            let mut games = DepthStats::default();
+            match check_win_slim(&child).and_then(Player::from_i32) {
+                Some(Player::X) => games.x_wins += 1,
+                Some(Player::O) => games.o_wins += 1,
+                None if get_selectable(&child) == 0 => games.draws += 1,
+                None => games.add(
+                    &explore(&child, player.invert(), args.depth.map(|g| g.saturating_sub(1)))
+                        .totals(),
+                ),
+            }
+            moves.add_row(vec![
+                Cell::new(cell_number(cell)),
+                Cell::new(Outcome::from_score(score)),
+                Cell::new(games.x_wins + games.o_wins + games.draws),
+                Cell::new(games.x_wins),
+                Cell::new(games.o_wins),
+                Cell::new(games.draws),
+            ]);
+        }
+        println!("{}", moves);
+    }
+
+    let analytics = helper.update_command_usage(ToolsAnalytics::EXPLORE);
+
+    Ok(Output {
+        message: None,
+        code: if analytics.is_ok() {
+            ResultCode::Success
+        } else {
+            ResultCode::SuccessAnalyticsFailed
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{explore, is_reachable};
+    use crate::commands::start::{actions::matrix_from_notation, game::Player};
+
+    #[test]
+    fn test_full_tree_matches_known_counts() {
+        let stats = explore(&[[0; 3]; 3], Player::X, None);
+        let nodes: Vec<u64> = stats.depths.iter().map(|g| g.nodes).collect();
+        assert_eq!(
+            nodes,
+            vec![9, 72, 504, 3024, 15120, 54720, 148176, 200448, 127872]
+        );
+
+        let total = stats.totals();
+        assert_eq!(total.x_wins + total.o_wins + total.draws, 255168);
+        assert_eq!((total.x_wins, total.o_wins, total.draws), (131184, 77904, 46080));
+        assert_eq!(stats.positions.len(), 5478);
+        assert_eq!(stats.canonical.len(), 765);
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let stats = explore(&[[0; 3]; 3], Player::X, Some(2));
+        assert_eq!(stats.depths.len(), 2);
+        assert_eq!(stats.positions.len(), 1 + 9 + 72);
+    }
+
+    #[test]
+    fn test_only_reachable_boards() {
+        let reachable = |notation: &str| is_reachable(&matrix_from_notation(notation).unwrap());
+        assert!(reachable("........."));
+        assert!(reachable("X...O...."));
+        assert!(reachable("XXXOO...."));
+        assert!(reachable("OOOXX.X.."));
+        // Too many of one side
+        assert!(!reachable("XX......."));
+        assert!(!reachable("O........"));
+        // Both sides have a line
+        assert!(!reachable("XXXOOO..."));
+        // X has won but O is to move, or O has won with X to move
+        assert!(!reachable("XXX.OO.O."));
+        assert!(!reachable("OOOXX.XX."));
+    }
+}
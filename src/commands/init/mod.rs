@@ -3,7 +3,7 @@ use inquire::Text;
 
 use crate::{
     services::{
-        config::AppConfig, db::records::ToolsAnalytics, helper::HelperService
+        config::{check_profile_name, AppConfig}, db::records::ToolsAnalytics, helper::HelperService
     },
     Failure, Output, ResultCode, ToolResult,
 };
@@ -16,7 +16,7 @@ pub struct ArgsInit {
 }
 
 const ABOUT_INIT: &'static str = "Initialize toolkit";
-const LONG_ABOUT_INIT: &'static str = "Initialize the toolkit by setting a name.";
+const LONG_ABOUT_INIT: &'static str = "Initialize the toolkit by setting a name. The name gets a profile of its own, or switches to it if it already has one; see \"profile\" to manage them.";
 
 pub fn handle(args: ArgsInit, cfg: &mut AppConfig, helper: &HelperService) -> ToolResult {
     let name: String;
@@ -64,14 +64,20 @@ pub fn handle(args: ArgsInit, cfg: &mut AppConfig, helper: &HelperService) -> To
         name = args.name.clone().unwrap();
     }
 
-    cfg.user = Some(name);
+    check_profile_name(&name)?;
+    let created = cfg.add_profile(&name);
+    cfg.user = Some(name.clone());
 
     match cfg.update() {
         Ok(_) => {
             let analytics = helper.update_command_usage(ToolsAnalytics::INIT);
 
             Ok(Output {
-                message: Some("Name Set Successfully!".to_string()),
+                message: Some(if created {
+                    "Name Set Successfully!".to_string()
+                } else {
+                    format!("Switched to profile \"{}\"", name)
+                }),
                 code: if analytics.is_ok() { ResultCode::Success } else { ResultCode::SuccessAnalyticsFailed },
             })
         }
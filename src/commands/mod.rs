@@ -1,2 +1,13 @@
+pub mod analyze;
+pub mod arena;
+pub mod book;
+pub mod daily;
+pub mod explore;
 pub mod init;
-pub mod start;
\ No newline at end of file
+pub mod profile;
+pub mod puzzle;
+pub mod ratings;
+pub mod solve;
+pub mod start;
+pub mod tournament;
+pub mod train;
\ No newline at end of file
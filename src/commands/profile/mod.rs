@@ -0,0 +1,333 @@
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use comfy_table::{Cell, Table};
+
+use crate::{
+    AppResult, Failure, Output, ResultCode, ToolResult,
+    commands::start::computer::{Algorithm, Style},
+    services::{
+        config::{AppConfig, Profile, check_profile_name},
+        db::records::{MatchHistoryRecord, ToolsAnalytics},
+        helper::HelperService,
+    },
+};
+
+#[derive(Debug, Args)]
+#[command(about = ABOUT_PROFILE, long_about = LONG_ABOUT_PROFILE)]
+pub struct ArgsProfile {
+    #[command(subcommand)]
+    action: ProfileAction,
+}
+
+#[derive(Debug, Args)]
+struct ProfileSettings {
+    #[arg(short, long, value_parser = clap::value_parser!(u8).range(1..=4), help = "Default difficulty for \"start\"")]
+    difficulty: Option<u8>,
+    #[arg(short, long, help = "Default computer algorithm for \"start\"")]
+    algorithm: Option<Algorithm>,
+    #[arg(short, long, help = "Default computer playing style for \"start\"")]
+    style: Option<Style>,
+}
+
+#[derive(Debug, Subcommand)]
+enum ProfileAction {
+    #[command(about = "Create a profile")]
+    New {
+        name: String,
+        #[command(flatten)]
+        settings: ProfileSettings,
+        #[arg(long, action = clap::ArgAction::SetTrue, help = "Make it the active profile")]
+        switch: bool,
+    },
+    #[command(about = "List profiles")]
+    List,
+    #[command(about = "Make a profile the active one")]
+    Switch { name: String },
+    #[command(about = "Change the settings of a profile")]
+    Set {
+        #[arg(short, long, help = "Profile to change; the active one when not given")]
+        profile: Option<String>,
+        #[command(flatten)]
+        settings: ProfileSettings,
+        #[arg(long, action = clap::ArgAction::SetTrue, help = "Forget all settings first")]
+        clear: bool,
+    },
+    #[command(about = "Show the settings, rating and results of a profile")]
+    Show {
+        #[arg(help = "Profile to show; the active one when not given")]
+        name: Option<String>,
+    },
+    #[command(about = "Delete a profile with its rating, results and progress")]
+    Delete { name: String },
+}
+
+const ABOUT_PROFILE: &'static str = "Manage player profiles";
+const LONG_ABOUT_PROFILE: &'static str = "Manage the people playing on this machine. Each profile keeps its own rating, results and default game settings; the active one plays unless \"start --as\" picks another.";
+
+impl ProfileSettings {
+    fn apply(&self, profile: &mut Profile) {
+        if let Some(difficulty) = self.difficulty {
+            profile.difficulty = Some(difficulty);
+        }
+        if let Some(algorithm) = self.algorithm {
+            profile.algorithm = Some(algorithm);
+        }
+        if let Some(style) = self.style {
+            profile.style = Some(style);
+        }
+    }
+}
+
+fn not_found(name: &str) -> Failure {
+    Failure {
+        message: format!("No profile named \"{}\"", name),
+        trace: "See \"profile list\" for the profiles on this machine".to_string(),
+        code: ResultCode::InvalidArgs,
+    }
+}
+
+fn find_name(cfg: &AppConfig, name: Option<String>) -> AppResult<String> {
+    let name = name.or(cfg.user.clone()).unwrap_or_default();
+    match cfg.profile(&name) {
+        Some(g) => Ok(g.name.clone()),
+        None => Err(not_found(&name)),
+    }
+}
+
+pub fn handle(args: ArgsProfile, cfg: &mut AppConfig, helper: &HelperService) -> ToolResult {
+    let message = match args.action {
+        ProfileAction::New {
+            name,
+            settings,
+            switch,
+        } => {
+            let name = name.trim().to_string();
+            check_profile_name(&name)?;
+            if !cfg.add_profile(&name) {
+                return Err(Failure {
+                    message: format!("A profile named \"{}\" already exists", name),
+                    trace: "".to_string(),
+                    code: ResultCode::InvalidArgs,
+                });
+            }
+            settings.apply(cfg.profiles.last_mut().unwrap());
+            if switch {
+                cfg.user = Some(name.clone());
+            }
+            cfg.update()?;
+            Some(format!("Profile \"{}\" created", name))
+        }
+        ProfileAction::List => {
+            let ratings = helper.get_ratings()?;
+            let mut table = Table::new();
+            table.set_header(vec!["Name", "Rating", "Difficulty", "Algorithm", "Style"]);
+            for profile in cfg.profiles.iter() {
+                let active = cfg.user.as_ref() == Some(&profile.name);
+                table.add_row(vec![
+                    Cell::new(if active {
+                        format!("{} (active)", profile.name).bold().to_string()
+                    } else {
+                        profile.name.clone()
+                    }),
+                    Cell::new(
+                        ratings
+                            .ratings
+                            .get(&profile.name)
+                            .map(|g| format!("{:.0}", g.rating))
+                            .unwrap_or("-".to_string()),
+                    ),
+                    Cell::new(setting(profile.difficulty)),
+                    Cell::new(setting(profile.algorithm)),
+                    Cell::new(setting(profile.style)),
+                ]);
+            }
+            println!("{}", table);
+            None
+        }
+        ProfileAction::Switch { name } => {
+            let name = find_name(cfg, Some(name))?;
+            cfg.user = Some(name.clone());
+            cfg.update()?;
+            Some(format!("Playing as \"{}\"", name))
+        }
+        ProfileAction::Set {
+            profile,
+            settings,
+            clear,
+        } => {
+            let name = find_name(cfg, profile)?;
+            let profile = cfg.profiles.iter_mut().find(|g| g.name == name).unwrap();
+            if clear {
+                *profile = Profile {
+                    name: name.clone(),
+                    ..Profile::default()
+                };
+            }
+            settings.apply(profile);
+            cfg.update()?;
+            Some(format!("Settings of \"{}\" saved", name))
+        }
+        ProfileAction::Show { name } => {
+            let name = find_name(cfg, name)?;
+            let profile = cfg.profile(&name).unwrap();
+            let rating = helper.get_ratings()?.ratings.get(&name).cloned();
+            let results = ProfileResults::from_history(&helper.get_match_history()?, &name);
+
+            println!("{}", name.bold());
+            let mut table = Table::new();
+            table.add_row(vec![Cell::new("Difficulty"), Cell::new(setting(profile.difficulty))]);
+            table.add_row(vec![Cell::new("Algorithm"), Cell::new(setting(profile.algorithm))]);
+            table.add_row(vec![Cell::new("Style"), Cell::new(setting(profile.style))]);
+            table.add_row(vec![
+                Cell::new("Rating"),
+                Cell::new(match &rating {
+                    Some(g) => format!("{:.0} after {} rated game(s)", g.rating, g.games),
+                    None => "unrated".to_string(),
+                }),
+            ]);
+            table.add_row(vec![
+                Cell::new("Games"),
+                Cell::new(format!(
+                    "{} ({} won, {} drawn, {} lost)",
+                    results.wins + results.draws + results.losses,
+                    results.wins,
+                    results.draws,
+                    results.losses
+                )),
+            ]);
+            table.add_row(vec![
+                Cell::new("Series"),
+                Cell::new(format!("{} ({} won)", results.series, results.series_won)),
+            ]);
+            println!("{}", table);
+            None
+        }
+        ProfileAction::Delete { name } => {
+            let name = find_name(cfg, Some(name))?;
+            if cfg.user.as_ref() == Some(&name) {
+                return Err(Failure {
+                    message: format!("\"{}\" is the active profile", name),
+                    trace: "Switch to another profile before deleting it".to_string(),
+                    code: ResultCode::InvalidArgs,
+                });
+            }
+            cfg.profiles.retain(|g| g.name != name);
+            cfg.update()?;
+            helper.delete_profile_records(&name, |g| cfg.profile(g).is_some())?;
+            Some(format!("Profile \"{}\" deleted", name))
+        }
+    };
+
+    let analytics = helper.update_command_usage(ToolsAnalytics::PROFILE);
+
+    Ok(Output {
+        message,
+        code: if analytics.is_ok() {
+            ResultCode::Success
+        } else {
+            ResultCode::SuccessAnalyticsFailed
+        },
+    })
+}
+
+fn setting<T: ToString>(value: Option<T>) -> String {
+    value.map(|g| g.to_string()).unwrap_or("-".to_string())
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct ProfileResults {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+    series: u32,
+    series_won: u32,
+}
+
+impl ProfileResults {
+    /// Totals over every recorded match the profile played in, on either side.
+    fn from_history(history: &MatchHistoryRecord, name: &str) -> Self {
+        let mut results = ProfileResults::default();
+        for record in history.matches.iter() {
+            let Some(side) = record.players.iter().position(|g| g == name) else {
+                continue;
+            };
+            results.wins += record.wins(side);
+            results.draws += record.draws();
+            results.losses += record.wins(1 - side);
+            if record.best_of.is_some() {
+                results.series += 1;
+                if record.is_decided() && record.winner() == Some(side) {
+                    results.series_won += 1;
+                }
+            }
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProfileResults;
+    use crate::services::db::records::{
+        DELETED_PLAYER, MatchGame, MatchHistoryRecord, MatchRecord, ResultCause,
+    };
+
+    fn record(players: [&str; 2], best_of: Option<u32>, winners: &[Option<usize>]) -> MatchRecord {
+        MatchRecord {
+            date: "2024-01-01".to_string(),
+            players: players.map(String::from),
+            best_of,
+            games: winners
+                .iter()
+                .map(|&winner| MatchGame {
+                    x: 0,
+                    winner,
+                    cause: ResultCause::Board,
+                })
+                .collect(),
+            seed: None,
+        }
+    }
+
+    #[test]
+    fn test_results_count_both_sides() {
+        let history = MatchHistoryRecord {
+            matches: vec![
+                record(["alice", "bob"], Some(3), &[Some(0), Some(0)]),
+                record(["bob", "alice"], None, &[Some(0), None, Some(1)]),
+                record(["bob", "Computer 2 (Minimax)"], None, &[Some(1)]),
+            ],
+        };
+
+        assert_eq!(
+            ProfileResults::from_history(&history, "alice"),
+            ProfileResults {
+                wins: 3,
+                draws: 1,
+                losses: 1,
+                series: 1,
+                series_won: 1,
+            }
+        );
+        assert_eq!(ProfileResults::from_history(&history, "carol"), ProfileResults::default());
+    }
+
+    #[test]
+    fn test_deleting_keeps_matches_against_profiles() {
+        let mut history = MatchHistoryRecord {
+            matches: vec![
+                record(["alice", "bob"], Some(3), &[Some(0), Some(1), Some(1)]),
+                record(["alice", "Computer 2 (Minimax)"], None, &[Some(0)]),
+                record(["alice", "guest"], None, &[None]),
+                record(["bob", "carol"], None, &[Some(1)]),
+            ],
+        };
+        let bob = ProfileResults::from_history(&history, "bob");
+
+        history.remove_player("alice", |g| ["bob", "carol"].contains(&g));
+        assert_eq!(history.matches.len(), 2);
+        assert_eq!(history.matches[0].players, [DELETED_PLAYER, "bob"]);
+        assert_eq!(ProfileResults::from_history(&history, "bob"), bob);
+        assert_eq!(ProfileResults::from_history(&history, "alice"), ProfileResults::default());
+    }
+}
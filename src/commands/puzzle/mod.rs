@@ -0,0 +1,207 @@
+use clap::Args;
+use colored::Colorize;
+use comfy_table::{Cell, Color, Table};
+use puzzles::{Puzzle, builtin_puzzles, generate_puzzle};
+
+use crate::{
+    AppResult, Failure, Output, ResultCode, ToolResult,
+    commands::start::{
+        actions::{
+            cell_number, check_win, check_win_slim, clear_terminal, get_selectable,
+            position_to_cell, print_final_game_matrix, print_selectable_game_matrix,
+            select_position,
+        },
+        command_prompt::{CommandPrompt, GameCommand},
+        computer::{Outcome, score_moves},
+        game::GameMatrixWrapper,
+    },
+    services::{config::AppConfig, db::records::ToolsAnalytics, helper::HelperService},
+};
+
+pub mod puzzles;
+
+#[derive(Debug, Args)]
+#[command(about = ABOUT_PUZZLE, long_about = LONG_ABOUT_PUZZLE)]
+pub struct ArgsPuzzle {
+    #[arg(long, help = "Play a specific built-in puzzle")]
+    id: Option<String>,
+    #[arg(short, long, action = clap::ArgAction::SetTrue, help = "Generate a new puzzle with the engine")]
+    generate: bool,
+    #[arg(short = 'n', long, default_value = "2", value_parser = clap::value_parser!(u16).range(1..=4), help = "Moves to win for generated puzzles")]
+    moves: u16,
+    #[arg(short, long, action = clap::ArgAction::SetTrue, help = "List built-in puzzles and your progress")]
+    list: bool,
+    #[arg(long, action = clap::ArgAction::SetTrue, help = "Clear your puzzle progress")]
+    reset: bool,
+}
+
+const ABOUT_PUZZLE: &'static str = "Solve a puzzle";
+const LONG_ABOUT_PUZZLE: &'static str =
+    "Find the forced win in a TicTacToe position. Solved puzzles are remembered for each profile.";
+
+pub fn handle(args: ArgsPuzzle, cfg: &mut AppConfig, helper: &HelperService) -> ToolResult {
+    let profile = cfg.user.clone().unwrap_or_default();
+    if args.reset {
+        helper.reset_puzzle_progress(&profile)?;
+        return Ok(Output {
+            message: Some("Puzzle progress cleared".to_string()),
+            code: ResultCode::Success,
+        });
+    }
+
+    let progress = helper.get_puzzle_progress(&profile)?;
+
+    if args.list {
+        let mut table = Table::new();
+        table.set_header(vec!["Id", "To Move", "Win In", "Status"]);
+        for puzzle in builtin_puzzles() {
+            let solved = progress.solved.contains(&puzzle.id);
+            table.add_row(vec![
+                Cell::new(&puzzle.id),
+                Cell::new(puzzle.to_move),
+                Cell::new(puzzle.win_in),
+                match solved {
+                    true => Cell::new("Solved").fg(Color::Green),
+                    false => Cell::new("Unsolved").fg(Color::Yellow),
+                },
+            ]);
+        }
+        println!("{}", table);
+        println!("Solved {} puzzle(s) in {} attempt(s).", progress.solved.len(), progress.attempts);
+        return Ok(Output {
+            message: None,
+            code: ResultCode::Success,
+        });
+    }
+
+    let puzzle = match (&args.id, args.generate) {
+        (Some(id), _) => builtin_puzzles()
+            .into_iter()
+            .find(|g| &g.id == id)
+            .ok_or(Failure {
+                message: format!("Unknown puzzle \"{}\"", id),
+                trace: "Use \"puzzle --list\" to see the available puzzles".to_string(),
+                code: ResultCode::InvalidArgs,
+            })?,
+        (None, false) => match builtin_puzzles()
+            .into_iter()
+            .find(|g| !progress.solved.contains(&g.id))
+        {
+            Some(g) => g,
+            None => {
+                println!("{}", "All built-in puzzles solved, generating a new one.".green());
+                new_puzzle(args.moves, helper)?
+            }
+        },
+        (None, true) => new_puzzle(args.moves, helper)?,
+    };
+
+    let solved = solve_puzzle(&puzzle, helper)?;
+    helper.update_puzzle_progress(&profile, &puzzle.id, solved)?;
+
+    let analytics = helper.update_command_usage(ToolsAnalytics::PUZZLE);
+
+    Ok(Output {
+        message: if solved {
+            Some(format!("Puzzle {} solved!", puzzle.id))
+        } else {
+            None
+        },
+        code: if analytics.is_ok() {
+            ResultCode::Success
+        } else {
+            ResultCode::SuccessAnalyticsFailed
+        },
+    })
+}
+
+fn new_puzzle(moves: u16, helper: &HelperService) -> AppResult<Puzzle> {
+    helper.rng.with(|rng| generate_puzzle(moves, rng)).ok_or(Failure {
+        message: "Could not generate a puzzle".to_string(),
+        trace: format!("Reason: No win in {} was found, try fewer moves", moves),
+        code: ResultCode::InvalidArgs,
+    })
+}
+
+/// Walks the player through the puzzle, defending with the engine, until they win or go astray.
+pub fn solve_puzzle(puzzle: &Puzzle, helper: &HelperService) -> AppResult<bool> {
+    let mut game_matrix = GameMatrixWrapper(puzzle.board);
+    let player = puzzle.to_move;
+    let mut remaining = puzzle.win_in;
+
+    clear_terminal();
+    loop {
+        println!(
+            "{}",
+            format!(
+                "Puzzle {}: {} to play and win in {}",
+                puzzle.id, player, remaining
+            )
+            .bold()
+        );
+        print_selectable_game_matrix(&game_matrix);
+        println!();
+
+        let prompt = CommandPrompt::new(get_selectable(&game_matrix.value()), &["resign", "help"]);
+        let position = match prompt.prompt("Select the winning position: >").map_err(|e| {
+            Failure {
+                message: "Invalid input".to_string(),
+                trace: format!("Reason: {}", helper.generate_inquire_error(e)),
+                code: ResultCode::CancelOperation,
+            }
+        })? {
+            GameCommand::Move(g) => g as u16,
+            GameCommand::Resign => {
+                let gm = game_matrix.value();
+                let best = score_moves(&gm, player)[0];
+                println!(
+                    "\n{} The solution was cell {} ({}).",
+                    "Given up.".red().bold(),
+                    position_to_cell(&gm, best.0).map(cell_number).unwrap_or_default(),
+                    Outcome::from_score(best.1)
+                );
+                return Ok(false);
+            }
+            // Only resigning and help are offered
+            _ => {
+                clear_terminal();
+                prompt.print_help();
+                continue;
+            }
+        };
+
+        let gm = game_matrix.value();
+        let scores = score_moves(&gm, player);
+        let Some(played) = scores.iter().find(|g| g.0 == position) else {
+            clear_terminal();
+            println!("{}\n", "Select a valid position".red());
+            continue;
+        };
+
+        if !matches!(Outcome::from_score(played.1), Outcome::Win(n) if n <= remaining) {
+            let best = scores[0];
+            println!(
+                "\n{} Cell {} leads to a {}. The solution was cell {} ({}).",
+                "Incorrect.".red().bold(),
+                position_to_cell(&gm, position).map(cell_number).unwrap_or_default(),
+                Outcome::from_score(played.1),
+                position_to_cell(&gm, best.0).map(cell_number).unwrap_or_default(),
+                Outcome::from_score(best.1)
+            );
+            return Ok(false);
+        }
+
+        select_position(&mut game_matrix, position, player)?;
+        if check_win_slim(&game_matrix.value()).is_some() {
+            clear_terminal();
+            let win_cells = check_win(&mut game_matrix)?.1;
+            print_final_game_matrix(&game_matrix, win_cells);
+            return Ok(true);
+        }
+
+        let reply = score_moves(&game_matrix.value(), player.invert())[0].0;
+        select_position(&mut game_matrix, reply, player.invert())?;
+        remaining -= 1;
+        clear_terminal();
+    }
+}
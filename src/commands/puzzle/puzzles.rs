@@ -0,0 +1,107 @@
+use rand::{Rng, seq::IndexedRandom};
+
+use crate::commands::start::{
+    actions::{
+        ai_select, check_win_slim, get_selectable, matrix_from_notation, matrix_to_notation,
+        player_to_move,
+    },
+    computer::{Outcome, score_moves},
+    game::{GameMatrix, Player},
+    symmetry::canonicalize,
+};
+
+const GENERATOR_ATTEMPTS: usize = 5000;
+
+/// Built-in puzzles as (id, board, moves to win) with the side to move taken from the board.
+pub const BUILTIN_PUZZLES: [(&str, &str, u16); 8] = [
+    ("p01", "XX.OO....", 1),
+    ("p02", "X.O.XO...", 1),
+    ("p03", "XO...X.O.", 2),
+    ("p04", "XO.X..O..", 2),
+    ("p05", "XOX....O.", 2),
+    ("p06", "X....O.XO", 3),
+    ("p07", "XX......O", 3),
+    ("p08", ".XOX.....", 3),
+];
+
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    pub id: String,
+    pub board: GameMatrix,
+    pub to_move: Player,
+    pub win_in: u16,
+}
+
+impl Puzzle {
+    pub fn from_notation(id: &str, notation: &str, win_in: u16) -> Option<Self> {
+        let board = matrix_from_notation(notation)?;
+        Some(Puzzle {
+            id: id.to_string(),
+            board,
+            to_move: player_to_move(&board),
+            win_in,
+        })
+    }
+}
+
+pub fn builtin_puzzles() -> Vec<Puzzle> {
+    BUILTIN_PUZZLES
+        .iter()
+        .filter_map(|(id, notation, win_in)| Puzzle::from_notation(id, notation, *win_in))
+        .collect()
+}
+
+/// Shortest forced win for the side to move, if there is one.
+pub fn forced_win(board: &GameMatrix, player: Player) -> Option<u16> {
+    if check_win_slim(board).is_some() {
+        return None;
+    }
+    match Outcome::from_score(score_moves(board, player).first()?.1) {
+        Outcome::Win(n) => Some(n),
+        _ => None,
+    }
+}
+
+/// Plays random moves from an empty board until the side to move has a forced win in `win_in`.
+pub fn generate_puzzle<R: Rng>(win_in: u16, rng: &mut R) -> Option<Puzzle> {
+    for _ in 0..GENERATOR_ATTEMPTS {
+        let mut board: GameMatrix = [[0; 3]; 3];
+        let mut player = Player::X;
+
+        while check_win_slim(&board).is_none() && get_selectable(&board) > 0 {
+            if forced_win(&board, player) == Some(win_in) {
+                // Ids use the canonical board so mirrored puzzles count as the same one
+                let notation = matrix_to_notation(&board);
+                let id = format!("gen-{}", matrix_to_notation(&canonicalize(&board).0));
+                return Puzzle::from_notation(&id, &notation, win_in);
+            }
+
+            let positions: Vec<u16> = (1..=get_selectable(&board)).collect();
+            let position = *positions.choose(rng)?;
+            ai_select(&mut board, position, player);
+            player = player.invert();
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{builtin_puzzles, forced_win, generate_puzzle, BUILTIN_PUZZLES};
+
+    #[test]
+    fn test_builtin_puzzles_are_forced_wins() {
+        let puzzles = builtin_puzzles();
+        assert_eq!(puzzles.len(), BUILTIN_PUZZLES.len());
+        for puzzle in puzzles {
+            assert_eq!(forced_win(&puzzle.board, puzzle.to_move), Some(puzzle.win_in), "{}", puzzle.id);
+        }
+    }
+
+    #[test]
+    fn test_generated_puzzle_matches_request() {
+        let puzzle = generate_puzzle(2, &mut rand::rng()).expect("a win in 2 should be found");
+        assert_eq!(forced_win(&puzzle.board, puzzle.to_move), Some(2));
+    }
+}
@@ -0,0 +1,107 @@
+use clap::Args;
+use colored::Colorize;
+use comfy_table::{Cell, Table};
+
+use crate::{
+    Output, ResultCode, ToolResult,
+    services::{config::AppConfig, db::records::ToolsAnalytics, helper::HelperService},
+};
+
+#[derive(Debug, Args)]
+#[command(about = ABOUT_RATINGS, long_about = LONG_ABOUT_RATINGS)]
+pub struct ArgsRatings {
+    #[arg(long, action = clap::ArgAction::SetTrue, help = "Only list players")]
+    players: bool,
+    #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with = "players", help = "Only list computer levels")]
+    computers: bool,
+    #[arg(long, default_value = "0", help = "Hide anyone with fewer rated games")]
+    min_games: u32,
+    #[arg(long, action = clap::ArgAction::SetTrue, help = "Clear every rating")]
+    reset: bool,
+}
+
+const ABOUT_RATINGS: &'static str = "Show the ratings leaderboard";
+const LONG_ABOUT_RATINGS: &'static str = "Show the Elo ratings of every player and every computer difficulty and algorithm they have played. Everyone starts at 1200 and ratings move faster over the first 20 games; only games against the built-in computer, or hot-seat games between two profiles, count unless played with --coach or --unrated. Games against a computer tuned with --iterations, --think-time, --threads, --no-book or a playing style are left out, so each rating stays the strength of one difficulty.";
+
+pub fn handle(args: ArgsRatings, cfg: &mut AppConfig, helper: &HelperService) -> ToolResult {
+    if args.reset {
+        helper.reset_ratings()?;
+        return Ok(Output {
+            message: Some("All ratings cleared".to_string()),
+            code: ResultCode::Success,
+        });
+    }
+
+    let record = helper.get_ratings()?;
+    let leaderboard: Vec<_> = record
+        .leaderboard()
+        .into_iter()
+        .filter(|(_, g)| if g.computer { !args.players } else { !args.computers })
+        .filter(|(_, g)| g.games >= args.min_games)
+        .collect();
+
+    if leaderboard.is_empty() {
+        println!("No rated games yet, play one with \"start\"");
+    } else {
+        let mut table = Table::new();
+        table.set_header(vec!["#", "Name", "Rating", "Games", "Wins", "Draws", "Losses"]);
+        for (rank, (name, rating)) in leaderboard.iter().enumerate() {
+            let name = if cfg.user.as_ref() == Some(*name) {
+                name.bold().to_string()
+            } else {
+                name.to_string()
+            };
+            table.add_row(vec![
+                Cell::new(rank + 1),
+                Cell::new(name),
+                Cell::new(format!(
+                    "{:.0}{}",
+                    rating.rating,
+                    if rating.is_provisional() { "?" } else { "" }
+                )),
+                Cell::new(rating.games),
+                Cell::new(rating.wins),
+                Cell::new(rating.draws),
+                Cell::new(rating.losses),
+            ]);
+        }
+        println!("{}", table);
+        if leaderboard.iter().any(|(_, g)| g.is_provisional()) {
+            println!("? marks a provisional rating");
+        }
+    }
+
+    let analytics = helper.update_command_usage(ToolsAnalytics::RATINGS);
+
+    Ok(Output {
+        message: None,
+        code: if analytics.is_ok() {
+            ResultCode::Success
+        } else {
+            ResultCode::SuccessAnalyticsFailed
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::services::db::records::{INITIAL_RATING, RatingsRecord, expected_score};
+
+    #[test]
+    fn test_elo_updates() {
+        assert_eq!(expected_score(1500.0, 1500.0), 0.5);
+        assert!((expected_score(1600.0, 1200.0) - 0.909).abs() < 0.001);
+
+        let mut record = RatingsRecord::default();
+        let (player, computer) = record.rate("alice", "Computer 1 (Minimax)", true, 1.0);
+        assert_eq!((player, computer), (20.0, -20.0));
+        assert_eq!(record.ratings["alice"].rating, INITIAL_RATING + 20.0);
+        assert!(record.ratings["Computer 1 (Minimax)"].computer);
+
+        // A draw against a weaker opponent costs rating
+        let (player, _) = record.rate("alice", "Computer 1 (Minimax)", true, 0.5);
+        assert!(player < 0.0);
+        assert_eq!(record.ratings["alice"].games, 2);
+        assert_eq!(record.leaderboard()[0].0, "alice");
+    }
+}
@@ -0,0 +1,96 @@
+use std::{fs, path::PathBuf, time::Instant};
+
+use clap::Args;
+use colored::Colorize;
+use comfy_table::{Cell, Table};
+
+use crate::{
+    Failure, Output, ResultCode, ToolResult,
+    commands::start::{
+        actions::{DRAW, matrix_to_notation, player_to_move},
+        computer::Outcome,
+        oracle::Oracle,
+        symmetry::board_from_key,
+    },
+    services::{config::AppConfig, db::records::ToolsAnalytics, helper::HelperService},
+};
+
+#[derive(Debug, Args)]
+#[command(about = ABOUT_SOLVE, long_about = LONG_ABOUT_SOLVE)]
+pub struct ArgsSolve {
+    #[arg(short, long, help = "Write the table as CSV (board,to_move,value,outcome,distance)")]
+    export: Option<PathBuf>,
+}
+
+const ABOUT_SOLVE: &'static str = "Solve the game";
+const LONG_ABOUT_SOLVE: &'static str = "Generate the solved-game table behind the Perfect difficulty and hints, and report what it contains.";
+
+pub fn handle(args: ArgsSolve, _cfg: &mut AppConfig, helper: &HelperService) -> ToolResult {
+    let started = Instant::now();
+    let oracle = Oracle::generate();
+    let elapsed = started.elapsed();
+
+    let mut entries: Vec<(u32, i32)> = oracle.entries().collect();
+    entries.sort();
+
+    let (mut wins, mut draws, mut losses) = (0, 0, 0);
+    for (_, value) in entries.iter() {
+        match Outcome::from_score(*value) {
+            Outcome::Win(_) => wins += 1,
+            Outcome::Draw => draws += 1,
+            Outcome::Loss(_) => losses += 1,
+        }
+    }
+
+    let mut table = Table::new();
+    table.set_header(vec!["Positions", "Side To Move Wins", "Draws", "Side To Move Loses"]);
+    table.add_row(vec![
+        Cell::new(oracle.len()),
+        Cell::new(wins),
+        Cell::new(draws),
+        Cell::new(losses),
+    ]);
+    println!("{}", table);
+    println!(
+        "Solved {} unfinished positions (one per symmetry class) in {:.1?}. The empty board is a {} in {} plies.",
+        oracle.len(),
+        elapsed,
+        match oracle.value(&[[0; 3]; 3]) {
+            Some(DRAW) => "draw".green(),
+            _ => "decisive game".red(),
+        },
+        oracle.distance(&[[0; 3]; 3]).unwrap_or_default()
+    );
+
+    if let Some(path) = args.export.as_ref() {
+        let mut csv = String::from("board,to_move,value,outcome,distance\n");
+        for (key, value) in entries.iter() {
+            let gm = board_from_key(*key);
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                matrix_to_notation(&gm),
+                player_to_move(&gm),
+                value,
+                Outcome::from_score(*value),
+                oracle.distance(&gm).unwrap_or_default()
+            ));
+        }
+        fs::write(path, csv).map_err(|e| Failure {
+            message: "Failed to export solved table".to_string(),
+            trace: format!("Reason: {}", e),
+            code: ResultCode::PathError,
+        })?;
+        println!("Table written to {}", path.display());
+    }
+
+    let analytics = helper.update_command_usage(ToolsAnalytics::SOLVE);
+
+    Ok(Output {
+        message: None,
+        code: if analytics.is_ok() {
+            ResultCode::Success
+        } else {
+            ResultCode::SuccessAnalyticsFailed
+        },
+    })
+}
@@ -4,7 +4,10 @@ use crate::{
     AppResult, Failure, commands::start::game::Player, services::config::codes::ResultCode,
 };
 
-use super::game::{GameMatrix, GameMatrixWrapper, WinData};
+use super::{
+    computer::Outcome,
+    game::{GameMatrix, GameMatrixWrapper, WinData},
+};
 
 pub const TIC_TAC_TOE_PRESET: &str = "     == |--        ";
 
@@ -50,6 +53,52 @@ pub fn print_selectable_game_matrix(game_matrix: &GameMatrixWrapper) -> u16 {
     current_selectable
 }
 
+pub fn print_hint_game_matrix(game_matrix: &GameMatrixWrapper, hints: &[(u16, Outcome)]) {
+    let gm = game_matrix.value();
+    let mut table = Table::new();
+    table.load_preset(TIC_TAC_TOE_PRESET);
+
+    let mut current_selectable: u16 = 0;
+
+    for row in gm.iter() {
+        let row: Vec<Cell> = row
+            .iter()
+            .map(|&cell| match cell {
+                x if x == Player::X.as_i32() => Cell::new(" X ")
+                    .fg(comfy_table::Color::Red)
+                    .add_attribute(Attribute::Bold),
+                x if x == Player::O.as_i32() => Cell::new(" O ")
+                    .fg(comfy_table::Color::Blue)
+                    .add_attribute(Attribute::Bold),
+                _ => {
+                    current_selectable += 1;
+                    let outcome = hints
+                        .iter()
+                        .find(|g| g.0 == current_selectable)
+                        .map(|g| g.1);
+                    match outcome {
+                        Some(outcome) => Cell::new(format!(" {}:{} ", current_selectable, outcome.label()))
+                            .fg(match outcome {
+                                Outcome::Win(_) => comfy_table::Color::Green,
+                                Outcome::Draw => comfy_table::Color::Yellow,
+                                Outcome::Loss(_) => comfy_table::Color::Magenta,
+                            })
+                            .add_attribute(Attribute::Bold),
+                        None => Cell::new(format!(" {} ", current_selectable))
+                            .fg(comfy_table::Color::Yellow)
+                            .add_attribute(Attribute::Bold),
+                    }
+                }
+            })
+            .collect();
+        table.add_row(row);
+    }
+
+    table.set_width(30);
+
+    println!("\n{}", table);
+}
+
 pub fn print_final_game_matrix(game_matrix: &GameMatrixWrapper, win_cells: Vec<(usize, usize)>) {
     let gm = game_matrix.value().clone();
     let mut table = Table::new();
@@ -110,7 +159,7 @@ pub fn select_position(
     game_matrix: &mut GameMatrixWrapper,
     position: u16,
     player: Player,
-) -> AppResult<()> {
+) -> AppResult<(usize, usize)> {
     let gm = game_matrix.value();
     let mut current_selectable: u16 = 0;
 
@@ -123,7 +172,7 @@ pub fn select_position(
                     current_selectable += 1;
                     if position == current_selectable {
                         game_matrix.set_position((i, j), player);
-                        return Ok(());
+                        return Ok((i, j));
                     }
                 }
             }
@@ -137,6 +186,114 @@ pub fn select_position(
     })
 }
 
+/// Maps a board cell (numbered 1-9, row by row) to its selectable position, if empty.
+pub fn cell_to_position(gm: &GameMatrix, cell: u16) -> Option<u16> {
+    if !(1..=9).contains(&cell) {
+        return None;
+    }
+    let (row, col) = (((cell - 1) / 3) as usize, ((cell - 1) % 3) as usize);
+    if gm[row][col] != 0 {
+        return None;
+    }
+
+    let mut current_selectable: u16 = 0;
+    for (i, cells) in gm.iter().enumerate() {
+        for (j, &cell) in cells.iter().enumerate() {
+            if cell == 0 {
+                current_selectable += 1;
+                if (i, j) == (row, col) {
+                    return Some(current_selectable);
+                }
+            }
+        }
+    }
+    None
+}
+
+pub fn position_to_cell(gm: &GameMatrix, position: u16) -> Option<(usize, usize)> {
+    let mut check_matrix = *gm;
+    ai_select(&mut check_matrix, position, Player::X)
+}
+
+pub fn cell_number(cell: (usize, usize)) -> u16 {
+    (cell.0 * 3 + cell.1) as u16 + 1
+}
+
+pub fn cell_from_number(cell: u16) -> (usize, usize) {
+    (((cell - 1) / 3) as usize, ((cell - 1) % 3) as usize)
+}
+
+/// Empty cells where `player` would complete a line right away.
+pub fn winning_cells(gm: &GameMatrix, player: Player) -> Vec<(usize, usize)> {
+    let mut check_matrix = *gm;
+    let mut cells = vec![];
+    for i in 1..=get_selectable(gm) {
+        if let Some(pos) = ai_select(&mut check_matrix, i, player) {
+            if check_win_slim(&check_matrix) == Some(player.as_i32()) {
+                cells.push(pos);
+            }
+            ai_unselect(&mut check_matrix, pos);
+        }
+    }
+    cells
+}
+
+pub fn cell_region(cell: (usize, usize)) -> &'static str {
+    match cell {
+        (1, 1) => "centre",
+        (0, 0) | (0, 2) | (2, 0) | (2, 2) => "corner",
+        _ => "edge",
+    }
+}
+
+pub fn describe_line(cells: &Vec<(usize, usize)>) -> String {
+    let names = ["top", "middle", "bottom"];
+    let cols = ["left", "middle", "right"];
+    match cells.as_slice() {
+        [a, b, ..] if a.0 == b.0 => format!("{} row", names[a.0]),
+        [a, b, ..] if a.1 == b.1 => format!("{} column", cols[a.1]),
+        [(0, 0), ..] => "diagonal".to_string(),
+        _ => "anti-diagonal".to_string(),
+    }
+}
+
+/// Parses a board written row by row as nine of `X`, `O` and `.`.
+pub fn matrix_from_notation(notation: &str) -> Option<GameMatrix> {
+    let cells: Vec<char> = notation.chars().filter(|g| !g.is_whitespace()).collect();
+    if cells.len() != 9 {
+        return None;
+    }
+
+    let mut gm: GameMatrix = [[0; 3]; 3];
+    for (idx, c) in cells.iter().enumerate() {
+        gm[idx / 3][idx % 3] = match c.to_ascii_uppercase() {
+            'X' => Player::X.as_i32(),
+            'O' => Player::O.as_i32(),
+            '.' | '-' | '_' => 0,
+            _ => return None,
+        };
+    }
+    Some(gm)
+}
+
+pub fn matrix_to_notation(gm: &GameMatrix) -> String {
+    gm.iter()
+        .flatten()
+        .map(|&g| match Player::from_i32(g) {
+            Some(Player::X) => 'X',
+            Some(Player::O) => 'O',
+            None => '.',
+        })
+        .collect()
+}
+
+/// The side to move, assuming X moved first.
+pub fn player_to_move(gm: &GameMatrix) -> Player {
+    let x = gm.iter().flatten().filter(|&&g| g == Player::X.as_i32()).count();
+    let o = gm.iter().flatten().filter(|&&g| g == Player::O.as_i32()).count();
+    if x > o { Player::O } else { Player::X }
+}
+
 pub fn check_win(game_matrix: &mut GameMatrixWrapper) -> AppResult<WinData> {
     let gm = game_matrix.value();
     for i in 0..gm.len() {
@@ -243,3 +400,80 @@ pub fn evaluate_board(gm: &GameMatrix, player: Player) -> Option<i32> {
         }
     }
 }
+
+pub const LINES: [[(usize, usize); 3]; 8] = [
+    [(0, 0), (0, 1), (0, 2)],
+    [(1, 0), (1, 1), (1, 2)],
+    [(2, 0), (2, 1), (2, 2)],
+    [(0, 0), (1, 0), (2, 0)],
+    [(0, 1), (1, 1), (2, 1)],
+    [(0, 2), (1, 2), (2, 2)],
+    [(0, 0), (1, 1), (2, 2)],
+    [(0, 2), (1, 1), (2, 0)],
+];
+
+/// True when the game can only end in a draw however it is played: every line either holds
+/// both marks or needs more marks than its side has moves left.
+pub fn is_dead_draw(gm: &GameMatrix) -> bool {
+    if check_win_slim(gm).is_some() {
+        return false;
+    }
+    let empty = gm.iter().flatten().filter(|&&g| g == 0).count();
+    let to_move = player_to_move(gm);
+
+    [Player::X, Player::O].iter().all(|&player| {
+        let moves_left = if player == to_move { empty.div_ceil(2) } else { empty / 2 };
+        LINES.iter().all(|line| {
+            let own = line.iter().filter(|&&(i, j)| gm[i][j] == player.as_i32()).count();
+            let blocked = line.iter().any(|&(i, j)| gm[i][j] == player.invert().as_i32());
+            blocked || 3 - own > moves_left
+        })
+    })
+}
+
+/// Positional estimate for a position `evaluate_board` cannot score yet, from `player`'s side:
+/// lines still open to one side only count for that side, more so with two marks in them,
+/// and having two immediate threats (a fork) counts most.
+pub fn evaluate_position(gm: &GameMatrix, player: Player) -> i32 {
+    let mut score = 0;
+    for line in LINES.iter() {
+        let own = line.iter().filter(|&&(i, j)| gm[i][j] == player.as_i32()).count();
+        let other = line
+            .iter()
+            .filter(|&&(i, j)| gm[i][j] == player.invert().as_i32())
+            .count();
+        match (own, other) {
+            (1, 0) => score += 1,
+            (2, 0) => score += 4,
+            (0, 1) => score -= 1,
+            (0, 2) => score -= 4,
+            _ => {}
+        }
+    }
+
+    if winning_cells(gm, player).len() >= 2 {
+        score += 10;
+    }
+    if winning_cells(gm, player.invert()).len() >= 2 {
+        score -= 10;
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_dead_draw, matrix_from_notation};
+
+    #[test]
+    fn test_dead_draw() {
+        let dead = |notation: &str| is_dead_draw(&matrix_from_notation(notation).unwrap());
+        assert!(!dead("........."));
+        // O can still complete the middle column
+        assert!(!dead("XOXXO.O.."));
+        // Every line holds both marks
+        assert!(dead("XOXXOOOX."));
+        // X has the only open line but just one move left to fill two cells
+        assert!(dead("XXOOOXX.."));
+        assert!(!dead("XXX.O.O.."));
+    }
+}
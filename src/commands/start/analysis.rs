@@ -0,0 +1,218 @@
+use colored::Colorize;
+use comfy_table::{Attribute, Cell, Color, Table};
+
+use crate::{AppResult, Failure, services::config::codes::ResultCode};
+
+use super::{
+    actions::{cell_number, cell_to_position, check_win, position_to_cell, print_final_game_matrix},
+    computer::{Outcome, score_moves},
+    game::{GameMatrixWrapper, Player},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveGrade {
+    Best,
+    Good,
+    Inaccuracy,
+    Blunder,
+}
+
+impl MoveGrade {
+    /// Compares the minimax score of the played move against the best available one.
+    pub fn from_scores(played: i32, best: i32) -> Self {
+        if played >= best {
+            return MoveGrade::Best;
+        }
+        match (Outcome::from_score(best), Outcome::from_score(played)) {
+            (Outcome::Loss(_), _) => MoveGrade::Good,
+            (_, Outcome::Loss(_)) => MoveGrade::Blunder,
+            (Outcome::Win(_), Outcome::Draw) => MoveGrade::Inaccuracy,
+            _ => MoveGrade::Good,
+        }
+    }
+
+    pub fn cell(&self) -> Cell {
+        match self {
+            MoveGrade::Best => Cell::new("Best").fg(Color::Green).add_attribute(Attribute::Bold),
+            MoveGrade::Good => Cell::new("Good").fg(Color::Cyan),
+            MoveGrade::Inaccuracy => Cell::new("Inaccuracy").fg(Color::Yellow),
+            MoveGrade::Blunder => Cell::new("Blunder").fg(Color::Red).add_attribute(Attribute::Bold),
+        }
+    }
+}
+
+pub struct MoveAnalysis {
+    pub player: Player,
+    pub cell: (usize, usize),
+    pub played: Outcome,
+    pub best: Outcome,
+    pub best_cells: Vec<(usize, usize)>,
+    pub grade: MoveGrade,
+}
+
+/// Replays a game from an empty board, X moving first, and grades every move.
+pub fn analyze_moves(moves: &[(usize, usize)]) -> AppResult<Vec<MoveAnalysis>> {
+    let mut game_matrix = GameMatrixWrapper::default();
+    let mut player = Player::X;
+    let mut analysis: Vec<MoveAnalysis> = vec![];
+
+    for (idx, cell) in moves.iter().enumerate() {
+        if check_win(&mut game_matrix)?.0.is_some() {
+            return Err(Failure {
+                message: "Invalid move list".to_string(),
+                trace: format!("Reason: The game is already over before move {}", idx + 1),
+                code: ResultCode::InvalidArgs,
+            });
+        }
+
+        let gm = game_matrix.value();
+        let position = cell_to_position(&gm, cell_number(*cell)).ok_or(Failure {
+            message: "Invalid move list".to_string(),
+            trace: format!(
+                "Reason: Cell {} is not available at move {}",
+                cell_number(*cell),
+                idx + 1
+            ),
+            code: ResultCode::InvalidArgs,
+        })?;
+
+        let scores = score_moves(&gm, player);
+        let best_score = scores[0].1;
+        let played_score = scores
+            .iter()
+            .find(|g| g.0 == position)
+            .map(|g| g.1)
+            .unwrap_or(best_score);
+
+        analysis.push(MoveAnalysis {
+            player,
+            cell: *cell,
+            played: Outcome::from_score(played_score),
+            best: Outcome::from_score(best_score),
+            best_cells: {
+                let mut cells: Vec<(usize, usize)> = scores
+                    .iter()
+                    .filter(|g| g.1 == best_score)
+                    .filter_map(|g| position_to_cell(&gm, g.0))
+                    .collect();
+                cells.sort();
+                cells
+            },
+            grade: MoveGrade::from_scores(played_score, best_score),
+        });
+
+        game_matrix.set_position(*cell, player);
+        player = player.invert();
+    }
+
+    Ok(analysis)
+}
+
+/// Index of the last blunder by the losing side, after which the winner never let the game go.
+pub fn losing_move(analysis: &[MoveAnalysis], winner: Option<Player>) -> Option<usize> {
+    let loser = winner?.invert();
+    analysis
+        .iter()
+        .rposition(|g| g.player == loser && g.grade == MoveGrade::Blunder)
+}
+
+pub fn print_analysis(moves: &[(usize, usize)], show_board: bool) -> AppResult<()> {
+    let analysis = analyze_moves(moves)?;
+
+    let mut game_matrix = GameMatrixWrapper::default();
+    for (g, cell) in analysis.iter().map(|g| (g.player, g.cell)) {
+        game_matrix.set_position(cell, g);
+    }
+    let win_data = check_win(&mut game_matrix)?;
+
+    if show_board {
+        print_final_game_matrix(&game_matrix, win_data.1.clone());
+    }
+
+    let mut table = Table::new();
+    table.set_header(vec!["#", "Player", "Cell", "Result", "Best", "Grade"]);
+    for (idx, g) in analysis.iter().enumerate() {
+        table.add_row(vec![
+            Cell::new(idx + 1),
+            Cell::new(g.player),
+            Cell::new(cell_number(g.cell)),
+            Cell::new(g.played),
+            Cell::new(format!(
+                "{} ({})",
+                g.best,
+                g.best_cells
+                    .iter()
+                    .map(|c| cell_number(*c).to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )),
+            g.grade.cell(),
+        ]);
+    }
+    println!("\n{}", "Game Analysis".bold());
+    println!("{}", table);
+
+    match losing_move(&analysis, win_data.0) {
+        Some(idx) => {
+            let g = &analysis[idx];
+            println!(
+                "\n{} {} played cell {} ({}) while {} was available.",
+                format!("The game was lost at move {}:", idx + 1).red().bold(),
+                g.player,
+                cell_number(g.cell),
+                g.played,
+                g.best
+            );
+        }
+        None => {
+            if win_data.0.is_none() {
+                println!("\n{}", "Nobody lost the game.".green());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MoveGrade, analyze_moves, losing_move};
+    use crate::commands::start::{actions::cell_from_number, game::Player};
+
+    fn grades(cells: &[u16]) -> Vec<MoveGrade> {
+        let moves: Vec<(usize, usize)> = cells.iter().map(|&g| cell_from_number(g)).collect();
+        analyze_moves(&moves).unwrap().iter().map(|g| g.grade).collect()
+    }
+
+    #[test]
+    fn test_grade_from_scores() {
+        assert_eq!(MoveGrade::from_scores(0, 0), MoveGrade::Best);
+        assert_eq!(MoveGrade::from_scores(9, 9), MoveGrade::Best);
+        // A slower win, or a slower loss when every move loses, is still fine
+        assert_eq!(MoveGrade::from_scores(7, 9), MoveGrade::Good);
+        assert_eq!(MoveGrade::from_scores(-9, -7), MoveGrade::Good);
+        assert_eq!(MoveGrade::from_scores(0, 7), MoveGrade::Inaccuracy);
+        assert_eq!(MoveGrade::from_scores(-7, 0), MoveGrade::Blunder);
+        assert_eq!(MoveGrade::from_scores(-7, 9), MoveGrade::Blunder);
+    }
+
+    #[test]
+    fn test_analyze_game() {
+        // An edge reply to the centre loses, and X then lets the win go with another edge
+        assert_eq!(
+            grades(&[5, 2, 8]),
+            vec![MoveGrade::Best, MoveGrade::Blunder, MoveGrade::Inaccuracy]
+        );
+        // A corner reply holds the draw
+        assert_eq!(grades(&[5, 1, 9]), vec![MoveGrade::Best; 3]);
+
+        // X forks with 4 and wins down the left column; the edge reply lost it for O
+        let moves: Vec<(usize, usize)> =
+            [5, 2, 1, 9, 4, 6, 7].iter().map(|&g| cell_from_number(g)).collect();
+        let analysis = analyze_moves(&moves).unwrap();
+        assert_eq!(losing_move(&analysis, Some(Player::X)), Some(1));
+        assert_eq!(losing_move(&analysis, None), None);
+
+        assert!(analyze_moves(&[(1, 1), (1, 1)]).is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use rand::{Rng, seq::IndexedRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppResult, Failure,
+    services::config::{codes::ResultCode, config_dir},
+};
+
+use super::{
+    actions::{
+        cell_from_number, cell_number, cell_to_position, matrix_from_notation, matrix_to_notation,
+    },
+    game::GameMatrix,
+    symmetry::{canonicalize, transform_cell, untransform_cell},
+};
+
+const BOOK_FILE: &str = "opening_book.json";
+
+/// Built-in openings as (board, [(cell, weight)]), cells numbered 1-9 row by row.
+const BUILTIN_BOOK: [(&str, &[(u16, u32)]); 10] = [
+    (".........", &[(5, 4), (1, 2), (3, 2), (7, 2), (9, 2)]),
+    ("....X....", &[(1, 1), (3, 1), (7, 1), (9, 1)]),
+    ("X........", &[(5, 1)]),
+    ("..X......", &[(5, 1)]),
+    ("......X..", &[(5, 1)]),
+    ("........X", &[(5, 1)]),
+    (".X.......", &[(5, 2), (1, 1), (3, 1), (8, 1)]),
+    ("...X.....", &[(5, 2), (1, 1), (7, 1), (6, 1)]),
+    (".....X...", &[(5, 2), (3, 1), (9, 1), (4, 1)]),
+    (".......X.", &[(5, 2), (7, 1), (9, 1), (2, 1)]),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BookMove {
+    pub cell: u16,
+    pub weight: u32,
+}
+
+/// Early positions, keyed by canonical board notation, mapped to weighted candidate moves.
+/// Entries for any orientation of a position are folded into its canonical form.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct OpeningBook {
+    pub entries: HashMap<String, Vec<BookMove>>,
+}
+
+impl OpeningBook {
+    pub fn builtin() -> Self {
+        let mut book = OpeningBook::default();
+        for (board, moves) in BUILTIN_BOOK.iter() {
+            let gm = matrix_from_notation(board).unwrap();
+            for &(cell, weight) in moves.iter() {
+                book.add(&gm, cell, weight);
+            }
+        }
+        book
+    }
+
+    /// Rebuilds the book with every entry in canonical orientation, dropping unreadable boards.
+    fn canonicalized(self) -> Self {
+        let mut book = OpeningBook::default();
+        for (board, moves) in self.entries.iter() {
+            if let Some(gm) = matrix_from_notation(board) {
+                for g in moves.iter().filter(|g| g.cell >= 1 && g.cell <= 9) {
+                    book.add(&gm, g.cell, g.weight);
+                }
+            }
+        }
+        book
+    }
+
+    pub fn path() -> AppResult<PathBuf> {
+        Ok(config_dir()?.join(BOOK_FILE))
+    }
+
+    /// The user's book file, empty if it does not exist yet.
+    pub fn load_user() -> AppResult<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(OpeningBook::default());
+        }
+
+        let data = fs::read_to_string(&path).map_err(|e| Failure {
+            message: "Failed to read opening book".to_string(),
+            trace: format!("Reason: {}", e),
+            code: ResultCode::PathError,
+        })?;
+        let book: OpeningBook = serde_json::from_str(&data).map_err(|e| Failure {
+            message: "Failed to parse opening book".to_string(),
+            trace: format!("Reason: {}: {}", path.display(), e),
+            code: ResultCode::SerializationError,
+        })?;
+        Ok(book.canonicalized())
+    }
+
+    /// The built-in book with the user's entries taking precedence.
+    pub fn load() -> AppResult<Self> {
+        let mut book = Self::builtin();
+        book.entries.extend(Self::load_user()?.entries);
+        Ok(book)
+    }
+
+    pub fn save_user(&self) -> AppResult<()> {
+        let data = serde_json::to_string_pretty(self).map_err(|e| Failure {
+            message: "Failed to serialize opening book".to_string(),
+            trace: format!("Reason: {}", e),
+            code: ResultCode::SerializationError,
+        })?;
+        fs::write(Self::path()?, data).map_err(|e| Failure {
+            message: "Failed to write opening book".to_string(),
+            trace: format!("Reason: {}", e),
+            code: ResultCode::PathError,
+        })
+    }
+
+    pub fn add(&mut self, gm: &GameMatrix, cell: u16, weight: u32) {
+        let (canonical, sym) = canonicalize(gm);
+        let cell = cell_number(transform_cell(cell_from_number(cell), sym));
+
+        let moves = self.entries.entry(matrix_to_notation(&canonical)).or_default();
+        match moves.iter_mut().find(|g| g.cell == cell) {
+            Some(g) => g.weight += weight,
+            None => moves.push(BookMove { cell, weight }),
+        }
+        moves.sort_by_key(|g| g.cell);
+    }
+
+    /// Picks a weighted book move for the position, as a selectable position.
+    pub fn choose<R: Rng>(&self, gm: &GameMatrix, rng: &mut R) -> Option<u16> {
+        let (canonical, sym) = canonicalize(gm);
+        let moves: Vec<&BookMove> = self
+            .entries
+            .get(&matrix_to_notation(&canonical))?
+            .iter()
+            .filter(|g| cell_to_position(&canonical, g.cell).is_some())
+            .collect();
+        let chosen = moves.choose_weighted(rng, |g| g.weight).ok()?;
+        let cell = untransform_cell(cell_from_number(chosen.cell), sym);
+        cell_to_position(gm, cell_number(cell))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OpeningBook;
+    use crate::commands::start::{
+        actions::{cell_to_position, matrix_from_notation, player_to_move, position_to_cell},
+        computer::score_moves,
+    };
+
+    #[test]
+    fn test_builtin_book_moves_are_best() {
+        for (notation, moves) in OpeningBook::builtin().entries.iter() {
+            let gm = matrix_from_notation(notation).unwrap();
+            let scores = score_moves(&gm, player_to_move(&gm));
+            for g in moves {
+                let position = cell_to_position(&gm, g.cell).unwrap();
+                let score = scores.iter().find(|s| s.0 == position).unwrap().1;
+                assert_eq!(score, scores[0].1, "{} cell {}", notation, g.cell);
+            }
+        }
+    }
+
+    #[test]
+    fn test_book_moves_follow_board_orientation() {
+        let book = OpeningBook::builtin();
+        let mut rng = rand::rng();
+        for notation in ["X........", "..X......", "......X..", "........X"] {
+            let gm = matrix_from_notation(notation).unwrap();
+            let position = book.choose(&gm, &mut rng).unwrap();
+            assert_eq!(position_to_cell(&gm, position), Some((1, 1)), "{}", notation);
+        }
+    }
+}
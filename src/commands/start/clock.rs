@@ -0,0 +1,198 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use strum_macros::Display;
+
+use crate::services::helper::parse_duration;
+
+use super::game::Player;
+
+/// Starting time on each clock and the time added after every move, `1m+2s`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+}
+
+pub fn parse_time_control(value: &str) -> Result<TimeControl, String> {
+    let (base, increment) = value.split_once('+').unwrap_or((value, "0"));
+    let base = parse_duration(base)?;
+    if base.is_zero() {
+        return Err("the clock needs some starting time".to_string());
+    }
+    Ok(TimeControl {
+        base,
+        increment: parse_duration(increment)?,
+    })
+}
+
+pub fn parse_move_time(value: &str) -> Result<Duration, String> {
+    let move_time = parse_duration(value)?;
+    if move_time.is_zero() {
+        return Err("each move needs some time".to_string());
+    }
+    Ok(move_time)
+}
+
+/// What happens to a player who runs out of time.
+#[derive(ValueEnum, Display, Debug, Clone, Copy, PartialEq)]
+pub enum OnFlag {
+    Lose,
+    Random,
+}
+
+#[derive(Debug)]
+struct ClockState {
+    /// Time left on the X and O clocks, when playing with a time control.
+    remaining: Option<[Duration; 2]>,
+    running: Option<(Player, Instant)>,
+}
+
+fn side(player: Player) -> usize {
+    if player == Player::X { 0 } else { 1 }
+}
+
+/// Per-move limit and chess clock for both sides of one game.
+pub struct Clock {
+    move_time: Option<Duration>,
+    control: Option<TimeControl>,
+    state: Arc<Mutex<ClockState>>,
+}
+
+impl Clock {
+    /// A clock for the limits that are set, None when there are none.
+    pub fn new(move_time: Option<Duration>, control: Option<TimeControl>) -> Option<Self> {
+        if move_time.is_none() && control.is_none() {
+            return None;
+        }
+        Some(Clock {
+            move_time,
+            control,
+            state: Arc::new(Mutex::new(ClockState {
+                remaining: control.map(|g| [g.base; 2]),
+                running: None,
+            })),
+        })
+    }
+
+    /// Time the player may spend on the coming move.
+    pub fn allowance(&self, player: Player) -> Duration {
+        let state = self.state.lock().unwrap();
+        let remaining = state.remaining.map(|g| g[side(player)]);
+        match (self.move_time, remaining) {
+            (Some(a), Some(b)) => a.min(b),
+            (Some(a), None) => a,
+            (None, Some(b)) => b,
+            (None, None) => Duration::MAX,
+        }
+    }
+
+    /// When the player whose clock is running runs out of time.
+    pub fn deadline(&self) -> Option<Instant> {
+        let (player, started) = self.state.lock().unwrap().running?;
+        started.checked_add(self.allowance(player))
+    }
+
+    /// Starts the player's clock, leaving it running when a retried move already started it.
+    pub fn start(&self, player: Player) {
+        let mut state = self.state.lock().unwrap();
+        if !state.running.is_some_and(|(g, _)| g == player) {
+            state.running = Some((player, Instant::now()));
+        }
+    }
+
+    /// Stops the running clock and charges the move; returns true when the player flagged.
+    pub fn stop(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let Some((player, started)) = state.running.take() else {
+            return false;
+        };
+        self.charge(&mut state, player, started.elapsed())
+    }
+
+    fn charge(&self, state: &mut ClockState, player: Player, elapsed: Duration) -> bool {
+        let mut flagged = self.move_time.is_some_and(|g| elapsed > g);
+        if let (Some(remaining), Some(control)) = (state.remaining.as_mut(), self.control) {
+            let left = &mut remaining[side(player)];
+            if elapsed > *left {
+                *left = Duration::ZERO;
+                flagged = true;
+            } else {
+                *left = *left - elapsed + control.increment;
+            }
+        }
+        flagged
+    }
+
+    /// Both clocks and the time left for the current move, shown ahead of the move prompt.
+    pub fn status(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let elapsed = |player: Player| match state.running {
+            Some((g, started)) if g == player => started.elapsed(),
+            _ => Duration::ZERO,
+        };
+
+        let mut parts = vec![];
+        if let Some(remaining) = state.remaining {
+            for player in [Player::X, Player::O] {
+                let marker = match state.running {
+                    Some((g, _)) if g == player => ">",
+                    _ => " ",
+                };
+                let left = remaining[side(player)].saturating_sub(elapsed(player));
+                parts.push(format!("{} {} {}", marker, player, format_time(left)));
+            }
+        }
+        if let (Some(move_time), Some((player, _))) = (self.move_time, state.running) {
+            parts.push(format!(
+                "Move {}",
+                format_time(move_time.saturating_sub(elapsed(player)))
+            ));
+        }
+        parts.join("  ")
+    }
+}
+
+fn format_time(time: Duration) -> String {
+    let tenths = time.as_millis() / 100;
+    format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Clock, ClockState, TimeControl, format_time, parse_move_time, parse_time_control};
+    use crate::commands::start::game::Player;
+
+    #[test]
+    fn test_time_control() {
+        assert_eq!(
+            parse_time_control("1m+2s"),
+            Ok(TimeControl {
+                base: Duration::from_secs(60),
+                increment: Duration::from_secs(2),
+            })
+        );
+        assert_eq!(parse_time_control("30s").map(|g| g.increment), Ok(Duration::ZERO));
+        assert!(parse_time_control("0+2s").is_err());
+        assert_eq!(parse_move_time("10s"), Ok(Duration::from_secs(10)));
+        assert!(parse_move_time("0s").is_err());
+        assert_eq!(format_time(Duration::from_millis(83_450)), "1:23.4");
+
+        let clock = Clock::new(Some(Duration::from_secs(5)), parse_time_control("10s+1s").ok()).unwrap();
+        let mut state = ClockState {
+            remaining: Some([Duration::from_secs(10); 2]),
+            running: None,
+        };
+        assert!(!clock.charge(&mut state, Player::X, Duration::from_secs(4)));
+        assert_eq!(state.remaining.unwrap()[0], Duration::from_secs(7));
+        // Over the move limit with time left on the clock still flags
+        assert!(clock.charge(&mut state, Player::O, Duration::from_secs(6)));
+        assert!(clock.charge(&mut state, Player::X, Duration::from_secs(8)));
+        assert_eq!(state.remaining.unwrap()[0], Duration::ZERO);
+    }
+}
@@ -0,0 +1,102 @@
+use super::{
+    actions::{
+        ai_select, cell_number, cell_region, check_win, describe_line, winning_cells,
+    },
+    computer::{Outcome, score_moves},
+    game::{GameMatrix, GameMatrixWrapper, Player},
+};
+
+/// Returns a warning when playing `position` turns a won or drawn position into a loss.
+pub fn check_blunder(gm: &GameMatrix, position: u16, player: Player) -> Option<String> {
+    let scores = score_moves(gm, player);
+    let best = Outcome::from_score(scores.first()?.1);
+    let played = Outcome::from_score(scores.iter().find(|g| g.0 == position)?.1);
+
+    match (best, played) {
+        (Outcome::Loss(_), _) => None,
+        (_, Outcome::Loss(_)) => {
+            let mut after = *gm;
+            ai_select(&mut after, position, player)?;
+            Some(format!(
+                "This move turns a {} into a {}: {}.",
+                match best {
+                    Outcome::Win(_) => "win",
+                    _ => "draw",
+                },
+                played,
+                explain_threat(&after, player.invert())
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Describes how `opponent` punishes the position they were just handed.
+fn explain_threat(gm: &GameMatrix, opponent: Player) -> String {
+    if let Some(cell) = winning_cells(gm, opponent).first() {
+        let mut game_matrix = GameMatrixWrapper(*gm);
+        game_matrix.set_position(*cell, opponent);
+        let line = check_win(&mut game_matrix).map(|g| g.1).unwrap_or_default();
+        return format!(
+            "this leaves {} free to complete the {} at cell {}",
+            opponent,
+            describe_line(&line),
+            cell_number(*cell)
+        );
+    }
+
+    let scores = score_moves(gm, opponent);
+    let Some(&(reply, score)) = scores.first() else {
+        return format!("{} can force a win", opponent);
+    };
+
+    let mut after = *gm;
+    let Some(cell) = ai_select(&mut after, reply, opponent) else {
+        return format!("{} can force a win", opponent);
+    };
+
+    if winning_cells(&after, opponent).len() >= 2 {
+        format!(
+            "this allows {} to fork from the {} at cell {}",
+            opponent,
+            cell_region(cell),
+            cell_number(cell)
+        )
+    } else {
+        format!(
+            "{} can force a {} starting at cell {}",
+            opponent,
+            Outcome::from_score(score),
+            cell_number(cell)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_blunder;
+    use crate::commands::start::{
+        actions::{cell_to_position, matrix_from_notation},
+        game::Player,
+    };
+
+    fn check(notation: &str, cell: u16, player: Player) -> Option<String> {
+        let gm = matrix_from_notation(notation).unwrap();
+        check_blunder(&gm, cell_to_position(&gm, cell).unwrap(), player)
+    }
+
+    #[test]
+    fn test_check_blunder() {
+        // X can win at 3, but playing 9 hands O the middle row
+        let warning = check("XX.OO....", 9, Player::X).unwrap();
+        assert!(warning.starts_with("This move turns a win into a"), "{}", warning);
+        assert!(warning.contains("cell 6"), "{}", warning);
+
+        // An edge reply to the centre loses a drawn game
+        let warning = check("....X....", 2, Player::O).unwrap();
+        assert!(warning.starts_with("This move turns a draw into a"), "{}", warning);
+
+        assert_eq!(check("....X....", 1, Player::O), None);
+        assert_eq!(check("XX.OO....", 3, Player::X), None);
+    }
+}
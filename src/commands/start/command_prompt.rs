@@ -0,0 +1,308 @@
+use std::{
+    io::Write,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use colored::Colorize;
+use comfy_table::Table;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    terminal,
+};
+use inquire::{
+    Autocomplete, CustomUserError, Text, autocompletion::Replacement, error::InquireError,
+    validator::Validation,
+};
+
+/// Everything that can be typed at the move prompt.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameCommand {
+    Move(i32),
+    Undo,
+    Hint,
+    Save(Option<PathBuf>),
+    Quit,
+    Help,
+    Flip,
+    Score,
+    Resign,
+    OfferDraw,
+}
+
+/// How often a timed prompt redraws the clocks.
+const REFRESH: Duration = Duration::from_millis(100);
+
+/// Names and descriptions, in the order `help` lists them.
+const COMMANDS: [(&str, &str); 10] = [
+    ("move", "move <n>, or just <n>: play the numbered position"),
+    ("undo", "take back the last move, and the computer's reply"),
+    ("hint", "show how each move plays out"),
+    ("draw?", "offer a draw"),
+    ("resign", "give up the game"),
+    ("flip", "swap sides with the computer"),
+    ("score", "show the score of the series"),
+    ("save", "save [file]: write the moves so far, to replay with \"analyze\""),
+    ("help", "list the commands available now"),
+    ("quit", "leave the game"),
+];
+
+impl GameCommand {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameCommand::Move(_) => "move",
+            GameCommand::Undo => "undo",
+            GameCommand::Hint => "hint",
+            GameCommand::Save(_) => "save",
+            GameCommand::Quit => "quit",
+            GameCommand::Help => "help",
+            GameCommand::Flip => "flip",
+            GameCommand::Score => "score",
+            GameCommand::Resign => "resign",
+            GameCommand::OfferDraw => "draw?",
+        }
+    }
+}
+
+impl FromStr for GameCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (word, rest) = match s.split_once(char::is_whitespace) {
+            Some((word, rest)) => (word.to_lowercase(), rest.trim()),
+            None => (s.to_lowercase(), ""),
+        };
+        let position = |value: &str| {
+            value
+                .parse::<i32>()
+                .map(GameCommand::Move)
+                .map_err(|_| format!("\"{}\" is not a position number", value))
+        };
+
+        match word.as_str() {
+            "" => Err("Enter a position number or a command".to_string()),
+            g if g.starts_with(|c: char| c.is_ascii_digit()) && rest.is_empty() => position(g),
+            "move" | "m" => position(rest),
+            "undo" | "u" => Ok(GameCommand::Undo),
+            "hint" | "h" | "?" => Ok(GameCommand::Hint),
+            "save" | "s" => Ok(GameCommand::Save((!rest.is_empty()).then(|| PathBuf::from(rest)))),
+            "quit" | "q" | "exit" => Ok(GameCommand::Quit),
+            "help" => Ok(GameCommand::Help),
+            "flip" => Ok(GameCommand::Flip),
+            "score" => Ok(GameCommand::Score),
+            "resign" => Ok(GameCommand::Resign),
+            "draw?" | "draw" => Ok(GameCommand::OfferDraw),
+            _ => Err(format!("Unknown command \"{}\", \"help\" lists them", word)),
+        }
+    }
+}
+
+/// Prompt for a position or one of the commands that make sense at this point of the game.
+#[derive(Clone)]
+pub struct CommandPrompt {
+    /// Highest position number on the board.
+    positions: u16,
+    commands: Vec<&'static str>,
+}
+
+impl CommandPrompt {
+    /// `commands` are the names enabled besides playing a move.
+    pub fn new(positions: u16, commands: &[&'static str]) -> Self {
+        CommandPrompt {
+            positions,
+            commands: commands.to_vec(),
+        }
+    }
+
+    fn check(&self, input: &str) -> Result<GameCommand, String> {
+        let command = input.parse::<GameCommand>()?;
+        match command {
+            GameCommand::Move(g) if g < 1 || g > self.positions as i32 => {
+                Err(format!("Pick a position from 1 to {}", self.positions))
+            }
+            GameCommand::Move(_) => Ok(command),
+            _ if !self.commands.contains(&command.name()) => {
+                Err(format!("\"{}\" isn't available now, \"help\" lists what is", command.name()))
+            }
+            _ => Ok(command),
+        }
+    }
+
+    pub fn prompt(&self, prompt_message: &str) -> Result<GameCommand, InquireError> {
+        let checker = self.clone();
+        let validator = move |input: &str| -> Result<Validation, CustomUserError> {
+            Ok(match checker.check(input) {
+                Ok(_) => Validation::Valid,
+                Err(e) => Validation::Invalid(e.into()),
+            })
+        };
+        let help = format!(
+            "Enter a position from 1 to {} or a command; \"help\" lists them",
+            self.positions
+        );
+        let input = Text::new(prompt_message)
+            .with_validator(validator)
+            .with_autocomplete(CommandCompleter(self.commands.clone()))
+            .with_help_message(&help)
+            .prompt()?;
+        // The validator has already accepted it
+        Ok(self.check(&input).unwrap_or(GameCommand::Help))
+    }
+
+    /// The prompt for a player on the clock: `status` is redrawn ahead of the input until a
+    /// command is entered, or `deadline` passes and None is returned without waiting for one.
+    pub fn prompt_timed(
+        &self,
+        prompt_message: &str,
+        deadline: Instant,
+        status: impl Fn() -> String,
+    ) -> Result<Option<GameCommand>, InquireError> {
+        let _raw_mode = RawMode::enable()?;
+        let mut completer = CommandCompleter(self.commands.clone());
+        let mut input = String::new();
+        let mut out = std::io::stderr();
+
+        loop {
+            write!(out, "\r[{}] {} {}\x1B[K", status(), prompt_message, input)?;
+            out.flush()?;
+
+            let now = Instant::now();
+            if now > deadline {
+                write!(out, "\r\n")?;
+                return Ok(None);
+            }
+            if !event::poll(REFRESH.min(deadline - now))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    write!(out, "\r\n")?;
+                    return Err(InquireError::OperationInterrupted);
+                }
+                KeyCode::Esc => {
+                    write!(out, "\r\n")?;
+                    return Err(InquireError::OperationCanceled);
+                }
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Tab => {
+                    if let Ok(Some(completion)) = completer.get_completion(&input, None) {
+                        input = completion;
+                    }
+                }
+                KeyCode::Enter => {
+                    write!(out, "\r\n")?;
+                    match self.check(&input) {
+                        Ok(command) => return Ok(Some(command)),
+                        Err(e) => write!(out, "{}\r\n", format!("# {}", e).red())?,
+                    }
+                    input.clear();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn print_help(&self) {
+        let mut table = Table::new();
+        table.set_header(vec!["Command", "What it does"]);
+        for (name, description) in COMMANDS.iter() {
+            if *name == "move" || self.commands.contains(name) {
+                table.add_row(vec![*name, *description]);
+            }
+        }
+        println!("{}", table);
+    }
+}
+
+/// Suggests the enabled commands; Tab completes the highlighted one or the only match.
+#[derive(Clone)]
+struct CommandCompleter(Vec<&'static str>);
+
+impl Autocomplete for CommandCompleter {
+    fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, CustomUserError> {
+        let input = input.trim().to_lowercase();
+        if input.is_empty() || input.starts_with(|c: char| c.is_ascii_digit()) {
+            return Ok(vec![]);
+        }
+        Ok(self
+            .0
+            .iter()
+            .filter(|g| g.starts_with(&input))
+            .map(|g| g.to_string())
+            .collect())
+    }
+
+    fn get_completion(
+        &mut self,
+        input: &str,
+        highlighted_suggestion: Option<String>,
+    ) -> Result<Replacement, CustomUserError> {
+        if highlighted_suggestion.is_some() {
+            return Ok(highlighted_suggestion);
+        }
+        let suggestions = self.get_suggestions(input)?;
+        Ok(match suggestions.as_slice() {
+            [only] => Some(only.clone()),
+            _ => None,
+        })
+    }
+}
+
+/// Keys reach the prompt one at a time, without echo, while this is alive.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> std::io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{CommandPrompt, GameCommand};
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!("5".parse(), Ok(GameCommand::Move(5)));
+        assert_eq!(" move 3 ".parse(), Ok(GameCommand::Move(3)));
+        assert_eq!("UNDO".parse(), Ok(GameCommand::Undo));
+        assert_eq!("draw?".parse(), Ok(GameCommand::OfferDraw));
+        assert_eq!("save".parse(), Ok(GameCommand::Save(None)));
+        assert_eq!(
+            "save Games/Last.txt".parse(),
+            Ok(GameCommand::Save(Some(PathBuf::from("Games/Last.txt"))))
+        );
+        assert!("move".parse::<GameCommand>().is_err());
+        assert!("castle".parse::<GameCommand>().is_err());
+    }
+
+    #[test]
+    fn test_only_available_commands_pass() {
+        let prompt = CommandPrompt::new(4, &["hint", "help"]);
+        assert_eq!(prompt.check("4"), Ok(GameCommand::Move(4)));
+        assert!(prompt.check("5").is_err());
+        assert!(prompt.check("0").is_err());
+        assert_eq!(prompt.check("h"), Ok(GameCommand::Hint));
+        assert!(prompt.check("undo").is_err());
+    }
+}
@@ -1,54 +1,235 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+    panic, thread,
+    time::Duration,
+};
+
+use clap::ValueEnum;
+use rand::{
+    Rng,
+    seq::{IndexedRandom, SliceRandom},
+};
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
 
-use crate::commands::start::actions::WIN;
+use crate::{
+    commands::start::actions::{DRAW, LOSE, WIN},
+    services::db::records::MenaceRecord,
+};
 
 use super::{
-    actions::{ai_select, ai_unselect, evaluate_board, get_selectable, select_position},
+    actions::{
+        ai_select, ai_unselect, cell_region, evaluate_board, get_selectable, player_to_move,
+        position_to_cell, select_position, winning_cells,
+    },
+    book::OpeningBook,
+    deepening::deepening_move,
     game::{GameMatrix, GameMatrixWrapper, Player},
+    mcts::mcts_move,
+    menace::menace_move,
+    oracle::oracle,
+    symmetry::canonical_key,
 };
 
 pub const EASY: u8 = 1;
 pub const NORMAL: u8 = 2;
-// pub const HARD: u8 = 3;
+pub const HARD: u8 = 3;
+pub const PERFECT: u8 = 4;
 
-pub fn make_move(gm: &mut GameMatrixWrapper, player: Player, difficulty: u8) {
-    let possibilities = get_selectable(&gm.value());
+/// Search effort of every search in this process, across threads: positions examined by
+/// minimax, the oracle and deepening, playouts run by MCTS.
+static NODES_SEARCHED: AtomicU64 = AtomicU64::new(0);
 
-    let mut check_matrix = gm.value();
+pub fn count_nodes(nodes: u64) {
+    NODES_SEARCHED.fetch_add(nodes, Ordering::Relaxed);
+}
 
-    if difficulty > EASY {
-        // Check for an immediate win
-        for i in 1..=possibilities {
-            if let Some(pos) = ai_select(&mut check_matrix, i, player) {
-                if evaluate_board(&check_matrix, player) == Some(WIN) {
-                    ai_unselect(&mut check_matrix, pos); // Undo move
-                    select_position(gm, i, player).unwrap();
-                    return;
-                }
-                ai_unselect(&mut check_matrix, pos);
-            }
+pub fn nodes_searched() -> u64 {
+    NODES_SEARCHED.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Win(u16),
+    Draw,
+    Loss(u16),
+}
+
+impl Outcome {
+    /// Converts a minimax score into the outcome for the side that made the move,
+    /// counting moves of the winning side (the scored move included for a win).
+    pub fn from_score(score: i32) -> Self {
+        if score > DRAW {
+            Outcome::Win(((WIN - score) as u16).div_ceil(2))
+        } else if score < DRAW {
+            Outcome::Loss((score - LOSE) as u16 / 2)
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Outcome::Win(n) => format!("W{}", n),
+            Outcome::Draw => "D".to_string(),
+            Outcome::Loss(n) => format!("L{}", n),
         }
+    }
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Win(n) => write!(f, "win in {}", n),
+            Outcome::Draw => write!(f, "draw"),
+            Outcome::Loss(n) => write!(f, "loss in {}", n),
+        }
+    }
+}
+
+#[derive(ValueEnum, Display, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Style {
+    Centre,
+    Corner,
+    Edge,
+}
+
+impl Style {
+    pub fn prefers(&self, cell: (usize, usize)) -> bool {
+        match self {
+            Style::Centre => cell_region(cell) == "centre",
+            Style::Corner => cell_region(cell) == "corner",
+            Style::Edge => cell_region(cell) == "edge",
+        }
+    }
+}
+
+#[derive(ValueEnum, Display, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Algorithm {
+    Minimax,
+    Mcts,
+    Deepening,
+    Menace,
+}
+
+#[derive(Debug, Clone)]
+pub struct EngineSettings {
+    pub difficulty: u8,
+    pub algorithm: Algorithm,
+    pub style: Option<Style>,
+    pub book: Option<OpeningBook>,
+    /// Playouts per move for MCTS, derived from the difficulty when not set.
+    pub iterations: Option<u32>,
+    /// Wall-clock budget per move for MCTS, taking precedence over the iteration count, and
+    /// for iterative deepening.
+    pub think_time: Option<Duration>,
+    /// Worker threads for MCTS and for minimax positions the oracle can't answer, all
+    /// available cores when not set. Iterative deepening searches on one thread.
+    pub threads: Option<usize>,
+    /// Learned matchboxes for the MENACE player, untrained when not set.
+    pub menace: Option<MenaceRecord>,
+}
+
+impl EngineSettings {
+    pub fn threads(&self) -> usize {
+        self.threads.unwrap_or_else(default_threads).max(1)
+    }
+
+    pub fn think_time(&self) -> Duration {
+        self.think_time.unwrap_or(Duration::from_millis(match self.difficulty {
+            EASY => 1,
+            NORMAL => 20,
+            HARD => 200,
+            _ => 1_000,
+        }))
+    }
+
+    pub fn mcts_iterations(&self) -> u32 {
+        self.iterations.unwrap_or(match self.difficulty {
+            EASY => 30,
+            NORMAL => 300,
+            HARD => 3_000,
+            _ => 20_000,
+        })
+    }
+}
+
+impl Default for EngineSettings {
+    fn default() -> Self {
+        Self {
+            difficulty: HARD,
+            algorithm: Algorithm::Minimax,
+            style: None,
+            book: None,
+            iterations: None,
+            think_time: None,
+            threads: None,
+            menace: None,
+        }
+    }
+}
+
+pub fn make_move<R: Rng>(
+    gm: &mut GameMatrixWrapper,
+    player: Player,
+    engine: &EngineSettings,
+    rng: &mut R,
+) -> (usize, usize) {
+    let difficulty = engine.difficulty;
+
+    if engine.algorithm == Algorithm::Mcts {
+        let cell = mcts_move(
+            &gm.value(),
+            player,
+            engine.mcts_iterations(),
+            engine.think_time,
+            engine.threads(),
+            rng,
+        );
+        gm.set_position(cell, player);
+        return cell;
+    }
+
+    if engine.algorithm == Algorithm::Menace {
+        let untrained = MenaceRecord::default();
+        let record = engine.menace.as_ref().unwrap_or(&untrained);
+        let cell = menace_move(record, &gm.value(), rng).unwrap();
+        gm.set_position(cell, player);
+        return cell;
+    }
+
+    if engine.algorithm == Algorithm::Deepening {
+        let cell = deepening_move(&gm.value(), player, engine.think_time());
+        gm.set_position(cell, player);
+        return cell;
+    }
+
+    // Perfect play comes straight from the oracle, with ties broken at random
+    if difficulty >= PERFECT {
+        let position = rank_moves(&gm.value(), player, engine.style, engine.threads(), rng)[0].0;
+        return select_position(gm, position, player).unwrap();
+    }
+
+    // Only full strength play follows the book, the lower levels rely on their own mistakes
+    if difficulty > NORMAL
+        && let Some(position) = engine.book.as_ref().and_then(|g| g.choose(&gm.value(), rng))
+    {
+        return select_position(gm, position, player).unwrap();
+    }
 
-        // Check for an immediate block (opponent's win)
-        let opponent = player.invert();
-        for i in 1..=possibilities {
-            if let Some(pos) = ai_select(&mut check_matrix, i, opponent) {
-                if evaluate_board(&check_matrix, opponent) == Some(WIN) {
-                    ai_unselect(&mut check_matrix, pos); // Undo move
-                    select_position(gm, i, player).unwrap();
-                    return;
-                }
-                ai_unselect(&mut check_matrix, pos);
+    if difficulty > EASY {
+        // Check for an immediate win, then for an immediate block (opponent's win)
+        for side in [player, player.invert()] {
+            if let Some(cell) = winning_cells(&gm.value(), side).choose(rng) {
+                gm.set_position(*cell, player);
+                return *cell;
             }
         }
     }
 
     // Use Minimax for Best Move
-    let mut best_scores: HashMap<u16, i32> = (1..=possibilities).map(|g| (g, 0)).collect();
-    generate_min_max_choices(&mut gm.value(), player, player, &mut best_scores, 0);
-
-    let mut action_map: Vec<(u16, i32)> = best_scores.iter().map(|g| (*g.0, *g.1)).collect();
-    action_map.sort_by(|a, b| b.1.cmp(&a.1));
+    let action_map = rank_moves(&gm.value(), player, engine.style, engine.threads(), rng);
 
     let selected_move = if difficulty > NORMAL {
         action_map[0].0
@@ -59,48 +240,128 @@ pub fn make_move(gm: &mut GameMatrixWrapper, player: Player, difficulty: u8) {
             action_map[0].0
         }
     };
-    select_position(gm, selected_move, player).unwrap();
+    select_position(gm, selected_move, player).unwrap()
 }
 
-fn generate_min_max_choices(
-    gm: &mut GameMatrix,
+/// Like `score_moves_on`, but equally scored moves are shuffled with the style's cells first.
+pub fn rank_moves<R: Rng>(
+    gm: &GameMatrix,
     player: Player,
-    ai_player: Player,
-    best_scores: &mut HashMap<u16, i32>,
-    depth: i32,
-) -> i32 {
-    match evaluate_board(gm, player) {
-        Some(score) => {
-            return score - depth;
-        }
-        None => {}
-    };
+    style: Option<Style>,
+    threads: usize,
+    rng: &mut R,
+) -> Vec<(u16, i32)> {
+    let mut action_map = score_moves_on(gm, player, threads);
+    action_map.shuffle(rng);
+    action_map.sort_by_key(|g| {
+        let preferred = style.is_some_and(|s| position_to_cell(gm, g.0).is_some_and(|c| s.prefers(c)));
+        (-g.1, !preferred)
+    });
+    action_map
+}
 
-    let spaces: u16 = get_selectable(&gm);
-    let mut best_score = if player == ai_player {
-        i32::MIN
-    } else {
-        i32::MAX
-    };
+/// Scores every selectable position for `player`, best first. Positions reached in normal
+/// play are answered from the solved-game oracle, anything else falls back to search.
+pub fn score_moves(gm: &GameMatrix, player: Player) -> Vec<(u16, i32)> {
+    score_moves_on(gm, player, default_threads())
+}
 
-    for i in 1..=spaces {
-        if let Some(pos) = ai_select(gm, i, player) {
-            let score =
-                generate_min_max_choices(gm, player.invert(), ai_player, best_scores, depth + 1);
+/// Like `score_moves`, with any search split across `threads` workers.
+pub fn score_moves_on(gm: &GameMatrix, player: Player, threads: usize) -> Vec<(u16, i32)> {
+    if player == player_to_move(gm)
+        && let Some(action_map) = oracle().score_moves(gm)
+    {
+        return action_map;
+    }
+    search_moves_parallel(gm, player, threads)
+}
 
-            if depth == 0 {
-                best_scores.insert(i, score);
-            }
+/// Scores every selectable position for `player` with minimax, best first, the root moves
+/// split across `threads` workers, each with its own cache. Every root move gets its exact
+/// value, so the result does not depend on the thread count.
+pub fn search_moves_parallel(gm: &GameMatrix, player: Player, threads: usize) -> Vec<(u16, i32)> {
+    let positions: Vec<u16> = (1..=get_selectable(gm)).collect();
+    if positions.is_empty() {
+        return vec![];
+    }
+    let chunk_size = positions.len().div_ceil(threads.max(1));
 
-            if player == ai_player {
-                best_score = best_score.max(score);
-            } else {
-                best_score = best_score.min(score);
-            }
+    let mut action_map: Vec<(u16, i32)> = thread::scope(|scope| {
+        let workers: Vec<_> = positions
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut cache: HashMap<u32, i32> = HashMap::new();
+                    chunk
+                        .iter()
+                        .filter_map(|&i| {
+                            let mut check_matrix = *gm;
+                            ai_select(&mut check_matrix, i, player)?;
+                            let value = generate_min_max_choices(
+                                &mut check_matrix,
+                                player.invert(),
+                                &mut cache,
+                            );
+                            Some((i, parent_score(value)))
+                        })
+                        .collect::<Vec<(u16, i32)>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|g| g.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    });
+
+    action_map.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    action_map
+}
+
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map(|g| g.get()).unwrap_or(1)
+}
+
+/// Turns the opponent's value after a move into the score of that move for the mover.
+pub fn parent_score(child_value: i32) -> i32 {
+    let score = -child_value;
+    score - score.signum()
+}
 
+pub fn hint_moves(gm: &GameMatrix, player: Player) -> Vec<(u16, Outcome)> {
+    score_moves(gm, player)
+        .into_iter()
+        .map(|(pos, score)| (pos, Outcome::from_score(score)))
+        .collect()
+}
+
+/// Negamax value of the position for `player`, the side to move: `WIN - n` when they win
+/// `n` plies from here, `LOSE + n` when they lose, `DRAW` otherwise. Symmetric positions
+/// share a value, so results are cached by canonical board.
+pub fn generate_min_max_choices(
+    gm: &mut GameMatrix,
+    player: Player,
+    cache: &mut HashMap<u32, i32>,
+) -> i32 {
+    count_nodes(1);
+    if let Some(score) = evaluate_board(gm, player) {
+        return score;
+    }
+
+    let key = canonical_key(gm);
+    if let Some(score) = cache.get(&key) {
+        return *score;
+    }
+
+    let mut best_score = i32::MIN;
+    for i in 1..=get_selectable(gm) {
+        if let Some(pos) = ai_select(gm, i, player) {
+            let score = parent_score(generate_min_max_choices(gm, player.invert(), cache));
+            best_score = best_score.max(score);
             ai_unselect(gm, pos);
         }
     }
 
+    cache.insert(key, best_score);
     best_score
 }
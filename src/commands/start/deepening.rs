@@ -0,0 +1,135 @@
+use std::{
+    cmp::Reverse,
+    time::{Duration, Instant},
+};
+
+use super::{
+    actions::{
+        ai_select, ai_unselect, check_win_slim, evaluate_position, get_selectable,
+        position_to_cell,
+    },
+    computer::count_nodes,
+    game::{GameMatrix, Player},
+};
+
+/// Terminal scores sit far above anything `evaluate_position` can return.
+const MATE: i32 = 1_000;
+
+struct Search {
+    deadline: Instant,
+    nodes: u64,
+}
+
+impl Search {
+    /// Depth-limited negamax with alpha-beta pruning, `None` once the deadline has passed.
+    fn negamax(
+        &mut self,
+        gm: &mut GameMatrix,
+        player: Player,
+        depth: u16,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> Option<i32> {
+        self.nodes += 1;
+        if check_win_slim(gm).is_some() {
+            return Some(-MATE + ply);
+        }
+        let spaces = get_selectable(gm);
+        if spaces == 0 {
+            return Some(0);
+        }
+        if depth == 0 {
+            return Some(evaluate_position(gm, player));
+        }
+        if Instant::now() >= self.deadline {
+            return None;
+        }
+
+        let mut best = i32::MIN + 1;
+        for i in 1..=spaces {
+            let pos = ai_select(gm, i, player)?;
+            let score = self.negamax(gm, player.invert(), depth - 1, ply + 1, -beta, -alpha);
+            ai_unselect(gm, pos);
+
+            let score = -score?;
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        Some(best)
+    }
+}
+
+/// Searches one ply deeper at a time until `budget` runs out and plays the best move from the
+/// deepest search that finished. The first ply always completes, so a move is always found.
+pub fn deepening_move(gm: &GameMatrix, player: Player, budget: Duration) -> (usize, usize) {
+    let mut search = Search {
+        deadline: Instant::now() + budget,
+        nodes: 0,
+    };
+    let spaces = get_selectable(gm);
+    let mut order: Vec<u16> = (1..=spaces).collect();
+    let mut best_move = order[0];
+
+    for depth in 1..=spaces {
+        if depth > 1 && Instant::now() >= search.deadline {
+            break;
+        }
+
+        let mut scored: Vec<(u16, i32)> = vec![];
+        let mut completed = true;
+        for &i in order.iter() {
+            let mut board = *gm;
+            ai_select(&mut board, i, player);
+            // Leaves are scored before the deadline is checked, so depth 1 always completes
+            match search.negamax(&mut board, player.invert(), depth - 1, 1, -MATE - 1, MATE + 1) {
+                Some(score) => scored.push((i, -score)),
+                None => {
+                    completed = false;
+                    break;
+                }
+            }
+        }
+        if !completed {
+            break;
+        }
+
+        // Best first, so the next iteration searches the most promising moves early
+        scored.sort_by_key(|g| Reverse(g.1));
+        order = scored.iter().map(|g| g.0).collect();
+        best_move = order[0];
+
+        if scored[0].1.abs() >= MATE - 9 {
+            break;
+        }
+    }
+
+    count_nodes(search.nodes);
+    position_to_cell(gm, best_move).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::deepening_move;
+    use crate::commands::start::game::Player;
+
+    #[test]
+    fn test_deepening_finds_win_and_block() {
+        let budget = Duration::from_millis(50);
+        assert_eq!(deepening_move(&[[1, 1, 0], [2, 2, 0], [0, 0, 0]], Player::X, budget), (0, 2));
+        assert_eq!(deepening_move(&[[1, 1, 0], [0, 2, 0], [0, 0, 0]], Player::O, budget), (0, 2));
+        // Answering a corner opening anywhere but the centre loses
+        assert_eq!(deepening_move(&[[1, 0, 0], [0, 0, 0], [0, 0, 0]], Player::O, budget), (1, 1));
+    }
+
+    #[test]
+    fn test_deepening_answers_without_time() {
+        let cell = deepening_move(&[[0; 3]; 3], Player::X, Duration::ZERO);
+        assert!(cell.0 < 3 && cell.1 < 3);
+    }
+}
@@ -0,0 +1,238 @@
+use std::{
+    cell::Cell,
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{AppResult, Failure, services::config::codes::ResultCode};
+
+use super::{
+    actions::{cell_from_number, matrix_to_notation},
+    game::{GameMatrix, Player},
+};
+
+const PROTOCOL_VERSION: u32 = 2;
+
+/// An opponent running as a separate program, spoken to one line at a time:
+///
+/// ```text
+/// host -> engine                      engine -> host
+/// hello 2                             ready [name]
+/// move <id> <board> <X|O> <millis>    <id> <cell 1-9>
+/// result <X|O|draw>
+/// quit
+/// ```
+///
+/// Boards are nine characters row by row, `X`, `O` or `.` for an empty cell. The engine must
+/// answer `hello` and every `move` within the timeout; `result` and `quit` need no answer.
+/// Each `move` has a new id for the answer to repeat, so an answer that comes in after its
+/// request timed out is told apart and skipped. Lines the engine prints starting with `#`
+/// are ignored, stderr is passed through.
+pub struct ExternalEngine {
+    pub name: String,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    timeout: Duration,
+    /// Id of the last move requested.
+    request: Cell<u32>,
+}
+
+impl ExternalEngine {
+    /// Starts the program and waits for it to answer the handshake. The command is split into
+    /// words the way a shell would, so quotes keep paths with spaces together.
+    pub fn spawn(command: &str, timeout: Duration) -> AppResult<Self> {
+        let parts = shlex::split(command).ok_or(Failure {
+            message: format!("Engine command \"{}\" has an unclosed quote", command),
+            trace: "".to_string(),
+            code: ResultCode::InvalidArgs,
+        })?;
+        let (program, args) = parts.split_first().ok_or(Failure {
+            message: "Engine command is empty".to_string(),
+            trace: "".to_string(),
+            code: ResultCode::InvalidArgs,
+        })?;
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| Failure {
+                message: format!("Failed to start engine \"{}\"", command),
+                trace: format!("Reason: {}", e),
+                code: ResultCode::EngineError,
+            })?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if !line.trim_start().starts_with('#') && sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = ExternalEngine {
+            name: command.to_string(),
+            child,
+            stdin,
+            lines,
+            timeout,
+            request: Cell::new(0),
+        };
+
+        let reply = engine
+            .send(&format!("hello {}", PROTOCOL_VERSION))
+            .and_then(|_| engine.receive(Instant::now() + timeout))
+            .map_err(|e| Failure {
+                message: format!("Engine \"{}\" did not start", command),
+                trace: format!("Reason: {}", e),
+                code: ResultCode::EngineError,
+            })?;
+        match reply.split_once(' ') {
+            Some(("ready", name)) if !name.trim().is_empty() => engine.name = name.trim().to_string(),
+            None if reply == "ready" => {}
+            _ => {
+                return Err(Failure {
+                    message: format!("Engine \"{}\" did not start", command),
+                    trace: format!("Reason: expected \"ready\", got \"{}\"", reply),
+                    code: ResultCode::EngineError,
+                });
+            }
+        }
+
+        Ok(engine)
+    }
+
+    fn send(&self, line: &str) -> Result<(), String> {
+        let mut stdin = &self.stdin;
+        writeln!(stdin, "{}", line)
+            .and_then(|_| stdin.flush())
+            .map_err(|e| format!("engine stopped reading: {}", e))
+    }
+
+    fn receive(&self, deadline: Instant) -> Result<String, String> {
+        match self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(line) => Ok(line.trim().to_string()),
+            Err(RecvTimeoutError::Timeout) => {
+                Err(format!("no answer within {}ms", self.timeout.as_millis()))
+            }
+            Err(RecvTimeoutError::Disconnected) => Err("engine exited".to_string()),
+        }
+    }
+
+    /// Asks for a move; an error means the engine failed to produce a legal one in time.
+    pub fn request_move(&self, gm: &GameMatrix, player: Player) -> Result<(usize, usize), String> {
+        let id = self.request.get() + 1;
+        self.request.set(id);
+        self.send(&format!(
+            "move {} {} {} {}",
+            id,
+            matrix_to_notation(gm),
+            player,
+            self.timeout.as_millis()
+        ))?;
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            if let Some(cell) = parse_move(&self.receive(deadline)?, id, gm) {
+                return cell;
+            }
+        }
+    }
+
+    pub fn send_result(&self, winner: Option<Player>) {
+        let _ = self.send(&format!(
+            "result {}",
+            winner.map(|g| g.to_string()).unwrap_or("draw".to_string())
+        ));
+    }
+}
+
+/// The cell in an engine's answer to move request `id`, or None when it answers an earlier
+/// request.
+fn parse_move(reply: &str, id: u32, gm: &GameMatrix) -> Option<Result<(usize, usize), String>> {
+    let Some((reply_id, cell)) = reply.split_once(' ') else {
+        return Some(Err(format!("\"{}\" is not an id and a cell number", reply)));
+    };
+    match reply_id.parse::<u32>() {
+        Ok(g) if g < id => return None,
+        Ok(g) if g == id => {}
+        _ => return Some(Err(format!("\"{}\" is not an answer to move {}", reply, id))),
+    }
+
+    let cell = cell.trim();
+    Some(
+        cell.parse::<u16>()
+            .ok()
+            .filter(|g| (1..=9).contains(g))
+            .map(cell_from_number)
+            .ok_or(format!("\"{}\" is not a cell number", cell))
+            .and_then(|g| match gm[g.0][g.1] {
+                0 => Ok(g),
+                _ => Err(format!("cell {} is already taken", cell)),
+            }),
+    )
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        // Give the engine a moment to exit on its own before killing it
+        let _ = self.send("quit");
+        for _ in 0..10 {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{ExternalEngine, parse_move};
+    use crate::commands::start::game::Player;
+
+    #[test]
+    fn test_parse_move() {
+        let gm = [[1, 0, 0], [0, 2, 0], [0, 0, 0]];
+        assert_eq!(parse_move("4 2", 4, &gm), Some(Ok((0, 1))));
+        assert_eq!(parse_move("4  9 ", 4, &gm), Some(Ok((2, 2))));
+        // An answer to an earlier request is skipped
+        assert_eq!(parse_move("3 2", 4, &gm), None);
+        assert!(matches!(parse_move("4 1", 4, &gm), Some(Err(_))));
+        assert!(matches!(parse_move("4 5", 4, &gm), Some(Err(_))));
+        assert!(matches!(parse_move("4 0", 4, &gm), Some(Err(_))));
+        assert!(matches!(parse_move("4 10", 4, &gm), Some(Err(_))));
+        assert!(matches!(parse_move("4 two", 4, &gm), Some(Err(_))));
+        assert!(matches!(parse_move("5 2", 4, &gm), Some(Err(_))));
+        assert!(matches!(parse_move("2", 4, &gm), Some(Err(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_late_answer_is_skipped() {
+        // Answers the first move after its timeout, then the second one at once
+        let script = "read l; echo ready late; read l; sleep 0.6; echo 1 1; read l; echo 2 2";
+        let engine = ExternalEngine::spawn(
+            &format!("sh -c '{}'", script),
+            Duration::from_millis(400),
+        )
+        .unwrap();
+        assert_eq!(engine.name, "late");
+
+        let gm = [[0; 3]; 3];
+        assert!(engine.request_move(&gm, Player::X).unwrap_err().starts_with("no answer"));
+        assert_eq!(engine.request_move(&gm, Player::X), Ok((0, 1)));
+    }
+}
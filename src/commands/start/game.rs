@@ -1,17 +1,35 @@
+use std::{fs, path::PathBuf};
+
 use clap::ValueEnum;
 use colored::Colorize;
+use comfy_table::Table;
+use inquire::Confirm;
+use rand::Rng;
 use strum_macros::{Display, EnumIter};
 
 use crate::{
     AppResult, Failure,
-    services::{config::codes::ResultCode, helper::HelperService},
+    commands::daily::{date_string, today},
+    services::{
+        config::codes::ResultCode,
+        db::records::{MatchRecord, ResultCause},
+        helper::HelperService,
+    },
 };
 
 use super::{
     actions::{
-        check_win, clear_terminal, print_final_game_matrix, print_selectable_game_matrix,
+        cell_number, check_win, clear_terminal, get_selectable, is_dead_draw, player_to_move,
+        print_final_game_matrix, print_hint_game_matrix, print_selectable_game_matrix,
         select_position,
-    }, computer::make_move, number_prompt::NumberPrompt
+    },
+    analysis::print_analysis,
+    clock::{Clock, OnFlag},
+    coach::check_blunder,
+    computer::{EngineSettings, Outcome, hint_moves, make_move},
+    external::ExternalEngine,
+    command_prompt::{CommandPrompt, GameCommand},
+    menace::learn,
 };
 
 pub type GameMatrix = [[i32; 3]; 3];
@@ -62,19 +80,59 @@ impl Player {
     }
 }
 
+pub struct GameOptions {
+    pub player: Option<Player>,
+    pub multi_player: bool,
+    pub engine: EngineSettings,
+    /// Plays the computer's side instead of `engine` when set.
+    pub external: Option<ExternalEngine>,
+    pub coach: bool,
+    /// Offers `hint`, `undo` and `flip`; off where the result counts for more than the game.
+    pub assists: bool,
+    /// Names of whoever plays X and O in a multiplayer game.
+    pub names: Option<[String; 2]>,
+    pub clock: Option<Clock>,
+    pub on_flag: OnFlag,
+    /// The series this game belongs to, for the `score` command.
+    pub series: Option<MatchRecord>,
+}
+
+/// How a game ended: the winner, `None` for a draw, and what decided it.
+pub struct GameOutcome {
+    pub winner: Option<Player>,
+    pub cause: ResultCause,
+    /// Side the person ended the game on against the computer; `flip` can change it.
+    pub player: Option<Player>,
+    /// Set when a hint, undo or flip was used.
+    pub assisted: bool,
+}
+
+impl GameOptions {
+    fn name(&self, player: Player) -> Option<&String> {
+        let names = self.names.as_ref()?;
+        Some(&names[if player == Player::X { 0 } else { 1 }])
+    }
+}
+
 pub struct PlayerTurn {
     player: Player,
+    /// Side the person plays against the computer.
+    human: Option<Player>,
+    assisted: bool,
     game_complete: bool,
     win_data: WinData,
+    history: Vec<(usize, usize)>,
+    forfeit: Option<String>,
+    cause: ResultCause,
+    /// Shown with the board on the next turn.
+    notice: Option<String>,
 }
 
 impl PlayerTurn {
     pub fn play(
         &mut self,
-        mut game_matrix: &mut GameMatrixWrapper,
-        _player: Option<Player>,
-        multi_player: bool,
-        difficulty: u8,
+        game_matrix: &mut GameMatrixWrapper,
+        options: &GameOptions,
         helper: &HelperService,
     ) -> AppResult<()> {
         let available_cells = print_selectable_game_matrix(game_matrix);
@@ -82,61 +140,339 @@ impl PlayerTurn {
             self.game_complete = true;
             return Ok(());
         }
-        if multi_player {
+        if let Some(notice) = self.notice.take() {
+            println!("\n{}", notice.yellow());
+        }
+        if let Some(clock) = &options.clock {
+            // A player left without time keeps moving at random
+            if clock.allowance(self.player).is_zero() {
+                self.play_random(game_matrix, helper);
+                return Ok(());
+            }
+            clock.start(self.player);
+        }
+
+        if options.multi_player || self.human == Some(self.player) {
             println!("");
-            let player_turn_message = match self.player {
-                Player::X => format!("{} Turn", self.player.to_string()).red().bold(),
-                Player::O => format!("{} Turn", self.player.to_string()).blue().bold(),
+            let prompt_message = if options.multi_player {
+                let player_turn_message = match options.name(self.player) {
+                    Some(name) => format!("{}'s Turn ({})", name, self.player),
+                    None => format!("{} Turn", self.player.to_string()),
+                };
+                let player_turn_message = match self.player {
+                    Player::X => player_turn_message.red().bold(),
+                    Player::O => player_turn_message.blue().bold(),
+                };
+                format!("{}: Select a position to play: >", player_turn_message)
+            } else {
+                format!("Select a position to play: >")
             };
-            let position = NumberPrompt::prompt(
-                format!("{}: Select a position to play: >", player_turn_message).as_str(),
-            )
+
+            let position = self.prompt_position(game_matrix, &prompt_message, options, helper)?;
+            // Resigning, agreeing a draw, undoing, flipping or running out of time leave the
+            // turn without a move
+            let Some(position) = position else {
+                options.clock.as_ref().map(|g| g.stop());
+                return Ok(());
+            };
+            if self.flagged(game_matrix, options, helper) {
+                return Ok(());
+            }
+
+            let cell = select_position(game_matrix, position as u16, self.player)?;
+            self.history.push(cell);
+            self.check_winner(game_matrix);
+            Ok(())
+        } else if let Some(engine) = &options.external {
+            match engine.request_move(&game_matrix.value(), self.player) {
+                Ok(_) if self.flagged(game_matrix, options, helper) => {}
+                Ok(cell) => {
+                    game_matrix.set_position(cell, self.player);
+                    self.history.push(cell);
+                    self.check_winner(game_matrix);
+                }
+                Err(reason) => {
+                    self.forfeit = Some(format!("{} forfeits: {}", engine.name, reason));
+                    self.cause = ResultCause::Forfeit;
+                    self.win_data = (Some(self.player.invert()), vec![]);
+                    self.game_complete = true;
+                }
+            }
+            Ok(())
+        } else {
+            // Keep the computer's thinking within what is left on its clock
+            let capped = match (&options.clock, options.engine.think_time) {
+                (Some(clock), Some(think_time)) => Some(EngineSettings {
+                    think_time: Some(think_time.min(clock.allowance(self.player) / 2)),
+                    ..options.engine.clone()
+                }),
+                _ => None,
+            };
+            let engine = capped.as_ref().unwrap_or(&options.engine);
+
+            let cell = helper
+                .rng
+                .with(|rng| make_move(game_matrix, self.player, engine, rng));
+            // Take the move back until the clock has had its say
+            game_matrix.0[cell.0][cell.1] = 0;
+            if self.flagged(game_matrix, options, helper) {
+                return Ok(());
+            }
+            game_matrix.set_position(cell, self.player);
+            self.history.push(cell);
+            self.check_winner(game_matrix);
+            Ok(())
+        }
+    }
+
+    fn label(&self, options: &GameOptions, player: Player) -> String {
+        match options.name(player) {
+            Some(name) => format!("{} ({})", name, player),
+            None => format!("Player {}", player),
+        }
+    }
+
+    /// Stops the clock once a move is chosen. A player out of time loses, or with
+    /// `OnFlag::Random` has a random move played instead; either way the chosen move is void
+    /// and this returns true.
+    fn flagged(
+        &mut self,
+        game_matrix: &mut GameMatrixWrapper,
+        options: &GameOptions,
+        helper: &HelperService,
+    ) -> bool {
+        let Some(clock) = &options.clock else {
+            return false;
+        };
+        if !clock.stop() {
+            return false;
+        }
+
+        let label = self.label(options, self.player);
+        match options.on_flag {
+            OnFlag::Lose => {
+                self.forfeit = Some(format!("{} ran out of time", label));
+                self.cause = ResultCause::Flag;
+                self.win_data = (Some(self.player.invert()), vec![]);
+                self.game_complete = true;
+            }
+            OnFlag::Random => {
+                self.notice = Some(format!("{} ran out of time, a random move was played", label));
+                self.play_random(game_matrix, helper);
+            }
+        }
+        true
+    }
+
+    fn play_random(&mut self, game_matrix: &mut GameMatrixWrapper, helper: &HelperService) {
+        let available = get_selectable(&game_matrix.value());
+        let position = helper.rng.with(|rng| rng.random_range(1..=available));
+        if let Ok(cell) = select_position(game_matrix, position, self.player) {
+            self.history.push(cell);
+            self.check_winner(game_matrix);
+        }
+    }
+
+    /// Asks for a position, running any commands given meanwhile. None when the turn ends
+    /// without a move: the game was resigned or drawn, or a move was undone or sides flipped.
+    fn prompt_position(
+        &mut self,
+        game_matrix: &mut GameMatrixWrapper,
+        prompt_message: &str,
+        options: &GameOptions,
+        helper: &HelperService,
+    ) -> AppResult<Option<i32>> {
+        let mut draw_offered = false;
+        loop {
+            let prompt = CommandPrompt::new(
+                get_selectable(&game_matrix.value()),
+                &self.commands(options, draw_offered),
+            );
+            let deadline = options.clock.as_ref().and_then(|g| Some((g, g.deadline()?)));
+            let input = match deadline {
+                Some((clock, deadline)) => {
+                    prompt.prompt_timed(prompt_message, deadline, || clock.status())
+                }
+                None => prompt.prompt(prompt_message).map(Some),
+            }
             .map_err(|e| Failure {
                 message: "Invalid input".to_string(),
                 trace: format!("Reason: {}", helper.generate_inquire_error(e)),
                 code: ResultCode::CancelOperation,
             })?;
+            let Some(input) = input else {
+                self.flagged(game_matrix, options, helper);
+                return Ok(None);
+            };
 
-            let res = select_position(game_matrix, position as u16, self.player);
-            self.check_winner(game_matrix);
-            res
-        } else {
-            if _player.is_some_and(|g| g == self.player) {
-                println!("");
-                let position =
-                    NumberPrompt::prompt(format!("Select a position to play: >").as_str())
-                        .map_err(|e| Failure {
-                            message: "Invalid input".to_string(),
-                            trace: format!("Reason: {}", helper.generate_inquire_error(e)),
+            match input {
+                GameCommand::Move(g) => {
+                    if !options.coach || self.confirm_move(game_matrix, g, helper)? {
+                        return Ok(Some(g));
+                    }
+                }
+                GameCommand::Hint => {
+                    self.assisted = true;
+                    self.show_hints(game_matrix);
+                }
+                GameCommand::Undo => {
+                    // Against the computer its reply is taken back too
+                    let count = if options.multi_player { 1 } else { 2 };
+                    for _ in 0..count {
+                        if let Some(cell) = self.history.pop() {
+                            game_matrix.0[cell.0][cell.1] = 0;
+                        }
+                    }
+                    self.assisted = true;
+                    return Ok(None);
+                }
+                GameCommand::Flip => {
+                    self.human = self.human.map(|g| g.invert());
+                    self.assisted = true;
+                    self.notice = Some(format!(
+                        "You now play {}, the computer plays {}",
+                        self.player.invert(),
+                        self.player
+                    ));
+                    return Ok(None);
+                }
+                GameCommand::Save(path) => match save_game(&self.history, path) {
+                    Ok(path) => println!("Moves saved to {}", path.display()),
+                    Err(e) => println!("{}", format!("{}: {}", e.message, e.trace).red()),
+                },
+                GameCommand::Score => {
+                    if let Some(series) = &options.series {
+                        print_scoreboard(series);
+                    }
+                }
+                GameCommand::Help => prompt.print_help(),
+                GameCommand::Quit => {
+                    if ask("Do you really want to quit the game (Yes/No)? ", helper)? {
+                        return Err(Failure {
+                            message: "Game quit".to_string(),
+                            trace: "Reason: The game was left unfinished".to_string(),
                             code: ResultCode::CancelOperation,
-                        })?;
-
-                let res = select_position(game_matrix, position as u16, self.player);
-                self.check_winner(game_matrix);
-                res
-            } else {
-                make_move(&mut game_matrix, self.player, difficulty);
-                self.check_winner(game_matrix);
-                Ok(())
+                        });
+                    }
+                }
+                GameCommand::Resign => {
+                    if ask("Do you really want to resign (Yes/No)? ", helper)? {
+                        self.forfeit = Some(format!("{} resigned", self.label(options, self.player)));
+                        self.cause = ResultCause::Resign;
+                        self.win_data = (Some(self.player.invert()), vec![]);
+                        self.game_complete = true;
+                        return Ok(None);
+                    }
+                }
+                GameCommand::OfferDraw => {
+                    draw_offered = true;
+                    if self.draw_accepted(game_matrix, options, helper)? {
+                        self.notice = Some("Draw agreed".to_string());
+                        self.cause = ResultCause::Agreement;
+                        self.win_data = (None, vec![]);
+                        self.game_complete = true;
+                        return Ok(None);
+                    }
+                    println!("{}", "The draw offer was declined".yellow());
+                }
             }
         }
     }
 
-    pub fn change_turns(&mut self) {
-        match self.player {
-            Player::X => {
-                self.player = Player::O;
+    /// Commands that make sense at this point of the game.
+    fn commands(&self, options: &GameOptions, draw_offered: bool) -> Vec<&'static str> {
+        let mut commands = vec![];
+        let undoable = if options.multi_player { 1 } else { 2 };
+        if options.assists {
+            commands.push("hint");
+            if self.history.len() >= undoable {
+                commands.push("undo");
             }
-            Player::O => {
-                self.player = Player::X;
+        }
+        if !draw_offered {
+            commands.push("draw?");
+        }
+        commands.push("resign");
+        if options.assists && !options.multi_player {
+            commands.push("flip");
+        }
+        if options.series.is_some() {
+            commands.push("score");
+        }
+        commands.extend(["save", "help", "quit"]);
+        commands
+    }
+
+    /// The other player is asked; the computer takes the draw unless it is winning.
+    fn draw_accepted(
+        &self,
+        game_matrix: &GameMatrixWrapper,
+        options: &GameOptions,
+        helper: &HelperService,
+    ) -> AppResult<bool> {
+        if options.multi_player {
+            return ask(
+                &format!(
+                    "{} offers a draw. {}, do you accept (Yes/No)? ",
+                    self.label(options, self.player),
+                    self.label(options, self.player.invert())
+                ),
+                helper,
+            );
+        }
+        let best = hint_moves(&game_matrix.value(), self.player);
+        Ok(!matches!(best.first(), Some((_, Outcome::Loss(_)))))
+    }
+
+    fn confirm_move(
+        &self,
+        game_matrix: &GameMatrixWrapper,
+        position: i32,
+        helper: &HelperService,
+    ) -> AppResult<bool> {
+        let Some(warning) = check_blunder(&game_matrix.value(), position as u16, self.player)
+        else {
+            return Ok(true);
+        };
+
+        println!("\n{} {}", "Coach:".bold().yellow(), warning.yellow());
+        Confirm::new("Play it anyway (Yes/No)? ")
+            .with_default(false)
+            .prompt()
+            .map_err(|e| Failure {
+                message: "Invalid input".to_string(),
+                trace: format!("Reason: {}", helper.generate_inquire_error(e)),
+                code: ResultCode::CancelOperation,
+            })
+    }
+
+    fn show_hints(&self, game_matrix: &GameMatrixWrapper) {
+        let hints = hint_moves(&game_matrix.value(), self.player);
+        print_hint_game_matrix(game_matrix, &hints);
+
+        println!("{}", format!("Hints for {}:", self.player).bold());
+        for (position, outcome) in hints.iter() {
+            let line = format!("  {} -> {}", position, outcome);
+            match outcome {
+                Outcome::Win(_) => println!("{}", line.green()),
+                Outcome::Draw => println!("{}", line.yellow()),
+                Outcome::Loss(_) => println!("{}", line.magenta()),
             }
         }
+        println!();
     }
 
     pub fn check_winner(&mut self, game_matrix: &mut GameMatrixWrapper) {
         let res = check_win(game_matrix).unwrap();
         if res.0.is_some() {
             self.game_complete = true;
+        } else if !self.game_complete
+            && get_selectable(&game_matrix.value()) > 0
+            && is_dead_draw(&game_matrix.value())
+        {
+            self.notice = Some("Neither side can complete a line any more".to_string());
+            self.cause = ResultCause::DeadDraw;
+            self.game_complete = true;
         }
         self.win_data = res;
     }
@@ -146,26 +482,71 @@ impl Default for PlayerTurn {
     fn default() -> Self {
         Self {
             player: Player::X,
+            human: None,
+            assisted: false,
             game_complete: false,
             win_data: (None, vec![]),
+            history: vec![],
+            forfeit: None,
+            cause: ResultCause::Board,
+            notice: None,
         }
     }
 }
 
+fn ask(message: &str, helper: &HelperService) -> AppResult<bool> {
+    Confirm::new(message)
+        .with_default(false)
+        .prompt()
+        .map_err(|e| Failure {
+            message: "Invalid input".to_string(),
+            trace: format!("Reason: {}", helper.generate_inquire_error(e)),
+            code: ResultCode::CancelOperation,
+        })
+}
+
+/// Writes the moves as cell numbers, the list `analyze` replays.
+fn save_game(history: &[(usize, usize)], path: Option<PathBuf>) -> AppResult<PathBuf> {
+    let path = path.unwrap_or_else(|| PathBuf::from(format!("tictactoe-{}.txt", date_string(today()))));
+    let moves: Vec<String> = history.iter().map(|g| cell_number(*g).to_string()).collect();
+    fs::write(&path, format!("{}\n", moves.join(","))).map_err(|e| Failure {
+        message: "Failed to save the game".to_string(),
+        trace: format!("Reason: {}", e),
+        code: ResultCode::PathError,
+    })?;
+    Ok(path)
+}
+
+pub fn print_scoreboard(series: &MatchRecord) {
+    let title = match series.best_of {
+        Some(n) => format!("Best of {}, after {} game(s)", n, series.games.len()),
+        None => format!("After {} game(s)", series.games.len()),
+    };
+    println!("\n{}", title.bold());
+
+    let mut table = Table::new();
+    table.set_header(vec![series.players[0].as_str(), "Draws", series.players[1].as_str()]);
+    table.add_row(vec![series.wins(0), series.draws(), series.wins(1)]);
+    println!("{}", table);
+}
+
+/// Plays one game to the end.
 pub fn gameloop(
     game_matrix: &mut GameMatrixWrapper,
-    player: Option<Player>,
-    multi_player: bool,
-    difficulty: u8,
+    options: &GameOptions,
     helper: &HelperService,
-) -> AppResult<()> {
-    let mut turn = PlayerTurn::default();
+) -> AppResult<GameOutcome> {
+    let mut turn = PlayerTurn {
+        human: options.player,
+        ..Default::default()
+    };
     clear_terminal();
     loop {
-        let res = turn.play(game_matrix, player, multi_player, difficulty, helper);
+        let res = turn.play(game_matrix, options, helper);
         match res {
             Ok(_) => {
-                turn.change_turns();
+                // Taken from the board, undo and flip don't simply alternate turns
+                turn.player = player_to_move(&game_matrix.value());
                 clear_terminal();
             }
             Err(e) => {
@@ -176,23 +557,58 @@ pub fn gameloop(
             }
         }
         if turn.game_complete {
-            turn.check_winner(game_matrix);
+            if turn.forfeit.is_none() {
+                turn.check_winner(game_matrix);
+            }
 
-            print_final_game_matrix(game_matrix, turn.win_data.1);
+            print_final_game_matrix(game_matrix, turn.win_data.1.clone());
+            if let Some(notice) = turn.notice.take() {
+                println!("\n{}", notice.yellow());
+            }
+            if let Some(reason) = &turn.forfeit {
+                println!("\n{}", reason.red());
+            }
+            if let Some(engine) = &options.external {
+                engine.send_result(turn.win_data.0);
+            }
 
             if turn.win_data.0.is_none() {
                 println!("\n{}", "The game was a draw!".bold().yellow());
             } else {
-                match turn.win_data.0.unwrap() {
+                let winner = turn.win_data.0.unwrap();
+                let message = format!("{} won the game!", turn.label(options, winner));
+                match winner {
                     Player::X => {
-                        println!("\n{}", "Player X won the game!".bold().red());
+                        println!("\n{}", message.bold().red());
                     }
                     Player::O => {
-                        println!("\n{}", "Player O won the game!".bold().blue());
+                        println!("\n{}", message.bold().blue());
                     }
                 }
             }
-            return Ok(());
+
+            // MENACE learns from every game it plays against a person, except those where it
+            // was shown hints against it, had moves taken back or its side switched
+            if let (Some(record), Some(human)) = (&options.engine.menace, turn.human) {
+                let mut record = record.clone();
+                if !turn.assisted {
+                    learn(&mut record, &turn.history, human.invert(), turn.win_data.0);
+                }
+                record.tally(turn.win_data.0.map(|g| g != human));
+                helper.save_menace(&record)?;
+            }
+
+            if let Ok(true) =
+                Confirm::new("Do you want to see an analysis of the game (Yes/No)? ").prompt()
+            {
+                print_analysis(&turn.history, false)?;
+            }
+            return Ok(GameOutcome {
+                winner: turn.win_data.0,
+                cause: turn.cause,
+                player: turn.human,
+                assisted: turn.assisted,
+            });
         }
     }
 }
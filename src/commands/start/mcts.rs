@@ -0,0 +1,266 @@
+use std::{
+    panic, thread,
+    time::{Duration, Instant},
+};
+
+use rand::{Rng, SeedableRng, seq::IndexedRandom};
+use rand_chacha::ChaCha8Rng;
+
+use super::{
+    actions::check_win_slim,
+    computer::count_nodes,
+    game::{GameMatrix, Player},
+};
+
+const EXPLORATION: f64 = std::f64::consts::SQRT_2;
+/// Independent trees per search. Fixed so the work done does not depend on the thread count.
+const TREES: usize = 4;
+
+struct Node {
+    board: GameMatrix,
+    to_move: Player,
+    parent: Option<usize>,
+    cell: Option<(usize, usize)>,
+    children: Vec<usize>,
+    untried: Vec<(usize, usize)>,
+    visits: u32,
+    /// Results for the side that moved into this node: 1 per win, 0.5 per draw.
+    reward: f64,
+}
+
+impl Node {
+    fn new(
+        board: GameMatrix,
+        to_move: Player,
+        parent: Option<usize>,
+        cell: Option<(usize, usize)>,
+    ) -> Self {
+        let untried = if check_win_slim(&board).is_some() {
+            vec![]
+        } else {
+            empty_cells(&board)
+        };
+        Node {
+            board,
+            to_move,
+            parent,
+            cell,
+            children: vec![],
+            untried,
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+
+    fn uct(&self, parent_visits: u32) -> f64 {
+        self.reward / self.visits as f64
+            + EXPLORATION * ((parent_visits as f64).ln() / self.visits as f64).sqrt()
+    }
+}
+
+fn empty_cells(gm: &GameMatrix) -> Vec<(usize, usize)> {
+    (0..9)
+        .map(|g| (g / 3, g % 3))
+        .filter(|&(i, j)| gm[i][j] == 0)
+        .collect()
+}
+
+/// Plays random moves to the end of the game and returns the winner, if any.
+fn rollout<R: Rng>(board: &GameMatrix, to_move: Player, rng: &mut R) -> Option<Player> {
+    let mut board = *board;
+    let mut player = to_move;
+    loop {
+        if let Some(winner) = check_win_slim(&board) {
+            return Player::from_i32(winner);
+        }
+        let cells = empty_cells(&board);
+        let &(i, j) = cells.choose(rng)?;
+        board[i][j] = player.as_i32();
+        player = player.invert();
+    }
+}
+
+/// Monte Carlo Tree Search with UCT, run as `TREES` independent trees spread over `threads`
+/// workers, picking the move with the most visits across them. Runs `iterations` playouts in
+/// total, or for `budget` instead when one is given. Each tree is seeded from `rng`, so with
+/// an iteration count the move only depends on the seed, not on the number of threads.
+/// The board must have at least one empty cell.
+pub fn mcts_move<R: Rng>(
+    gm: &GameMatrix,
+    player: Player,
+    iterations: u32,
+    budget: Option<Duration>,
+    threads: usize,
+    rng: &mut R,
+) -> (usize, usize) {
+    let seeds: Vec<u64> = (0..TREES).map(|_| rng.random()).collect();
+    let per_tree = iterations.div_ceil(TREES as u32);
+    let chunk_size = TREES.div_ceil(threads.clamp(1, TREES));
+    // Each worker grows its trees one after another within the budget
+    let budget = budget.map(|g| g / chunk_size as u32);
+
+    let results: Vec<Vec<((usize, usize), u32)>> = thread::scope(|scope| {
+        let workers: Vec<_> = seeds
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|&seed| {
+                            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                            search_tree(gm, player, per_tree, budget, &mut rng)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|g| g.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    });
+
+    let mut visits: Vec<((usize, usize), u32)> = empty_cells(gm).into_iter().map(|g| (g, 0)).collect();
+    for (cell, count) in results.into_iter().flatten() {
+        if let Some(g) = visits.iter_mut().find(|g| g.0 == cell) {
+            g.1 += count;
+        }
+    }
+    // Ties go to the first cell in board order
+    visits
+        .iter()
+        .rev()
+        .max_by_key(|g| g.1)
+        .map(|g| g.0)
+        .unwrap_or_else(|| empty_cells(gm)[0])
+}
+
+/// Grows a single tree and returns the visit count of each root move.
+fn search_tree<R: Rng>(
+    gm: &GameMatrix,
+    player: Player,
+    iterations: u32,
+    budget: Option<Duration>,
+    rng: &mut R,
+) -> Vec<((usize, usize), u32)> {
+    let started = Instant::now();
+    let mut nodes: Vec<Node> = vec![Node::new(*gm, player, None, None)];
+    let mut done: u32 = 0;
+
+    loop {
+        let finished = match budget {
+            Some(budget) => started.elapsed() >= budget,
+            None => done >= iterations,
+        };
+        if finished && done > 0 {
+            break;
+        }
+
+        // Selection
+        let mut current = 0;
+        while nodes[current].untried.is_empty() && !nodes[current].children.is_empty() {
+            let parent_visits = nodes[current].visits;
+            current = *nodes[current]
+                .children
+                .iter()
+                .max_by(|a, b| {
+                    nodes[**a]
+                        .uct(parent_visits)
+                        .total_cmp(&nodes[**b].uct(parent_visits))
+                })
+                .unwrap();
+        }
+
+        // Expansion
+        if !nodes[current].untried.is_empty() {
+            let idx = rng.random_range(0..nodes[current].untried.len());
+            let cell = nodes[current].untried.swap_remove(idx);
+            let mut board = nodes[current].board;
+            board[cell.0][cell.1] = nodes[current].to_move.as_i32();
+            let child = Node::new(board, nodes[current].to_move.invert(), Some(current), Some(cell));
+            nodes.push(child);
+            let child_idx = nodes.len() - 1;
+            nodes[current].children.push(child_idx);
+            current = child_idx;
+        }
+
+        // Simulation
+        let winner = rollout(&nodes[current].board, nodes[current].to_move, rng);
+
+        // Backpropagation
+        let mut node = Some(current);
+        while let Some(idx) = node {
+            let mover = nodes[idx].to_move.invert();
+            nodes[idx].visits += 1;
+            nodes[idx].reward += match winner {
+                Some(g) if g == mover => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            node = nodes[idx].parent;
+        }
+        done += 1;
+    }
+    count_nodes(done as u64);
+
+    nodes[0]
+        .children
+        .iter()
+        .filter_map(|g| nodes[*g].cell.map(|cell| (cell, nodes[*g].visits)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::mcts_move;
+    use crate::commands::start::{
+        actions::{check_win_slim, get_selectable},
+        computer::{EngineSettings, make_move},
+        game::{GameMatrixWrapper, Player},
+    };
+
+    #[test]
+    fn test_mcts_takes_win_and_blocks() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let cell = mcts_move(&[[1, 1, 0], [2, 2, 0], [0, 0, 0]], Player::X, 2_000, None, 2, &mut rng);
+        assert_eq!(cell, (0, 2));
+
+        let cell = mcts_move(&[[1, 1, 0], [0, 2, 0], [0, 0, 0]], Player::O, 2_000, None, 2, &mut rng);
+        assert_eq!(cell, (0, 2));
+    }
+
+    #[test]
+    fn test_mcts_is_independent_of_thread_count() {
+        let gm = [[1, 0, 0], [0, 0, 0], [0, 0, 2]];
+        let moves: Vec<(usize, usize)> = [1, 2, 3, 4]
+            .into_iter()
+            .map(|threads| mcts_move(&gm, Player::X, 800, None, threads, &mut ChaCha8Rng::seed_from_u64(3)))
+            .collect();
+        assert!(moves.iter().all(|g| *g == moves[0]));
+    }
+
+    #[test]
+    fn test_mcts_holds_minimax_to_a_draw() {
+        let mut rng = ChaCha8Rng::seed_from_u64(11);
+        let minimax = EngineSettings::default();
+        for mcts_side in [Player::X, Player::O] {
+            let mut game_matrix = GameMatrixWrapper::default();
+            let mut player = Player::X;
+            while check_win_slim(&game_matrix.value()).is_none()
+                && get_selectable(&game_matrix.value()) > 0
+            {
+                if player == mcts_side {
+                    let cell = mcts_move(&game_matrix.value(), player, 5_000, None, 4, &mut rng);
+                    game_matrix.set_position(cell, player);
+                } else {
+                    make_move(&mut game_matrix, player, &minimax, &mut rng);
+                }
+                player = player.invert();
+            }
+            assert_eq!(check_win_slim(&game_matrix.value()), None);
+        }
+    }
+}
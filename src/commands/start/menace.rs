@@ -0,0 +1,115 @@
+use rand::{Rng, seq::IndexedRandom};
+
+use crate::services::db::records::MenaceRecord;
+
+use super::{
+    actions::{cell_from_number, cell_number, matrix_to_notation},
+    game::{GameMatrix, Player},
+    symmetry::{canonicalize, transform_cell, untransform_cell},
+};
+
+/// Beads per empty cell in a new matchbox, by the mover's turn number as in the original MENACE.
+const INITIAL_BEADS: [u32; 5] = [4, 3, 2, 1, 1];
+const WIN_REWARD: u32 = 3;
+const DRAW_REWARD: u32 = 1;
+const LOSS_PENALTY: u32 = 1;
+
+fn initial_box(canonical: &GameMatrix) -> Vec<(u16, u32)> {
+    let placed = canonical.iter().flatten().filter(|g| **g != 0).count();
+    (0..9)
+        .map(|g| (g / 3, g % 3))
+        .filter(|&(i, j)| canonical[i][j] == 0)
+        .map(|cell| (cell_number(cell), INITIAL_BEADS[placed / 2]))
+        .collect()
+}
+
+/// Draws a bead from the position's matchbox, as a cell in the board's own orientation.
+/// A box that has lost all its beads starts over instead of resigning.
+pub fn menace_move<R: Rng>(
+    record: &MenaceRecord,
+    gm: &GameMatrix,
+    rng: &mut R,
+) -> Option<(usize, usize)> {
+    let (canonical, sym) = canonicalize(gm);
+    let beads = record
+        .boxes
+        .get(&matrix_to_notation(&canonical))
+        .filter(|g| g.iter().any(|b| b.1 > 0))
+        .cloned()
+        .unwrap_or_else(|| initial_box(&canonical));
+    let &(cell, _) = beads.choose_weighted(rng, |g| g.1).ok()?;
+    Some(untransform_cell(cell_from_number(cell), sym))
+}
+
+/// Adds beads for every move `side` made in a won or drawn game and takes one away after a loss.
+pub fn learn(
+    record: &mut MenaceRecord,
+    history: &[(usize, usize)],
+    side: Player,
+    winner: Option<Player>,
+) {
+    let mut gm: GameMatrix = [[0; 3]; 3];
+    let mut player = Player::X;
+    for &cell in history {
+        if player == side {
+            let (canonical, sym) = canonicalize(&gm);
+            let cell = cell_number(transform_cell(cell, sym));
+            let beads = record
+                .boxes
+                .entry(matrix_to_notation(&canonical))
+                .or_insert_with(|| initial_box(&canonical));
+            if beads.iter().all(|g| g.1 == 0) {
+                *beads = initial_box(&canonical);
+            }
+            if let Some(g) = beads.iter_mut().find(|g| g.0 == cell) {
+                g.1 = match winner {
+                    Some(w) if w == side => g.1 + WIN_REWARD,
+                    Some(_) => g.1.saturating_sub(LOSS_PENALTY),
+                    None => g.1 + DRAW_REWARD,
+                };
+            }
+        }
+        gm[cell.0][cell.1] = player.as_i32();
+        player = player.invert();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::{learn, menace_move};
+    use crate::{
+        commands::start::{actions::matrix_to_notation, game::Player, symmetry::canonicalize},
+        services::db::records::MenaceRecord,
+    };
+
+    fn box_key(gm: [[i32; 3]; 3]) -> String {
+        matrix_to_notation(&canonicalize(&gm).0)
+    }
+
+    #[test]
+    fn test_learning_adjusts_played_moves() {
+        let mut record = MenaceRecord::default();
+        let history = [(0, 0), (1, 1), (0, 1), (2, 2), (0, 2)];
+        learn(&mut record, &history, Player::X, Some(Player::X));
+        learn(&mut record, &history, Player::O, Some(Player::X));
+
+        let opening = &record.boxes["........."];
+        assert_eq!(opening.iter().find(|g| g.0 == 1).unwrap().1, 4 + 3);
+        let reply = &record.boxes[&box_key([[1, 0, 0], [0, 0, 0], [0, 0, 0]])];
+        assert_eq!(reply.iter().map(|g| g.1).sum::<u32>(), 8 * 4 - 1);
+    }
+
+    #[test]
+    fn test_moves_follow_board_orientation() {
+        let mut record = MenaceRecord::default();
+        // Teach the reply to a corner opening in one orientation, then play it in another
+        record.boxes.insert(box_key([[1, 0, 0], [0, 0, 0], [0, 0, 0]]), vec![(5, 1)]);
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        for gm in [[[0, 0, 1], [0, 0, 0], [0, 0, 0]], [[0, 0, 0], [0, 0, 0], [1, 0, 0]]] {
+            assert_eq!(menace_move(&record, &gm, &mut rng), Some((1, 1)));
+        }
+    }
+}
@@ -1,18 +1,41 @@
+use std::time::Duration;
+
 use actions::clear_terminal;
 use clap::{Args, command};
-use game::{GameMatrixWrapper, Player, gameloop};
-use inquire::{Confirm, Select};
+use colored::Colorize;
+use book::OpeningBook;
+use clock::{Clock, OnFlag, TimeControl, parse_move_time, parse_time_control};
+use computer::{Algorithm, EngineSettings, NORMAL, Style};
+use external::ExternalEngine;
+use game::{GameMatrixWrapper, GameOptions, Player, gameloop, print_scoreboard};
+use inquire::{Confirm, CustomUserError, Select, Text};
+use rand::seq::IndexedRandom;
 use strum::IntoEnumIterator;
 
 use crate::{
-    Failure, Output, ResultCode, ToolResult,
-    services::{config::AppConfig, db::records::ToolsAnalytics, helper::HelperService},
+    AppResult, Failure, Output, ResultCode, ToolResult,
+    commands::daily::{date_string, today},
+    services::{
+        config::{AppConfig, Profile},
+        db::records::{MatchGame, MatchRecord, ToolsAnalytics},
+        helper::{HelperService, parse_duration},
+    },
 };
 
-mod game;
-mod actions;
-mod computer;
-mod number_prompt;
+pub(crate) mod game;
+pub(crate) mod mcts;
+pub(crate) mod menace;
+pub(crate) mod actions;
+pub(crate) mod analysis;
+pub(crate) mod book;
+pub(crate) mod clock;
+pub(crate) mod command_prompt;
+mod coach;
+pub(crate) mod computer;
+pub(crate) mod deepening;
+pub(crate) mod external;
+pub(crate) mod oracle;
+pub(crate) mod symmetry;
 
 #[derive(Debug, Args)]
 #[command(about = ABOUT_START, long_about = LONG_ABOUT_START)]
@@ -21,23 +44,101 @@ pub struct ArgsStart {
     player: Option<Player>,
     #[arg(short, long, action = clap::ArgAction::SetTrue)]
     multiplayer: bool,
-    #[arg(short = 'd' , long, default_value="2", help="Game Difficulty; 1 = Easy, 2 = Normal, 3 = Hard")]
-    difficulty: u8,
+    #[arg(long = "as", value_name = "PROFILE", help="Play as this profile instead of the active one; give two with --multiplayer, X's player first, or be asked for names")]
+    profiles: Vec<String>,
+    #[arg(short = 'd' , long, help="Game Difficulty; 1 = Easy, 2 = Normal, 3 = Hard, 4 = Perfect [default: 2 or the profile's]")]
+    difficulty: Option<u8>,
+    #[arg(short = 'a', long, help="Computer search algorithm [default: minimax or the profile's]")]
+    algorithm: Option<Algorithm>,
+    #[arg(long, help="MCTS playouts per move; defaults from the difficulty")]
+    iterations: Option<u32>,
+    #[arg(long, value_parser = parse_duration, help="Time the computer may think per move with mcts or deepening, e.g. 500ms or 2s")]
+    think_time: Option<Duration>,
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..), help="Search threads for the mcts and minimax computers; defaults to all cores")]
+    threads: Option<u16>,
+    #[arg(short = 's', long, help="Computer playing style, preferred among equally good moves [default: the profile's]")]
+    style: Option<Style>,
+    #[arg(long, action = clap::ArgAction::SetTrue, help="Don't let the computer play from the opening book")]
+    no_book: bool,
+    #[arg(short = 'r', long, action = clap::ArgAction::SetTrue, help="Pick your side at random; X always moves first")]
+    random_player: bool,
+    #[arg(long, conflicts_with = "multiplayer", help="Play against an external program instead of the computer, e.g. \"./mybot --fast\"; quote paths with spaces")]
+    engine_cmd: Option<String>,
+    #[arg(long, default_value="5s", value_parser = parse_duration, help="How long the external engine may take to answer")]
+    engine_timeout: Duration,
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..), help="Play a series of this many games, alternating who moves first; hint, undo and flip are off")]
+    best_of: Option<u32>,
+    #[arg(long, action = clap::ArgAction::SetTrue, help="Warn before a move that throws away a won or drawn position")]
+    coach: bool,
+    #[arg(long, value_parser = parse_move_time, help="Time each side may take per move, e.g. 10s")]
+    move_time: Option<Duration>,
+    #[arg(long, value_parser = parse_time_control, help="Chess clock for each side, starting time plus time added per move, e.g. 1m+2s")]
+    clock: Option<TimeControl>,
+    #[arg(long, default_value="lose", help="What happens to a side that runs out of time: it loses, or a random move is played for it")]
+    on_flag: OnFlag,
+    #[arg(long, action = clap::ArgAction::SetTrue, help="Don't count the games towards your rating")]
+    unrated: bool,
 }
 
 const ABOUT_START: &'static str = "Start the game";
 const LONG_ABOUT_START: &'static str = "Start the TicTacToe Game";
 
 pub fn handle(args: ArgsStart, cfg: &mut AppConfig, helper: &HelperService) -> ToolResult {
-    let mut game_matrix: GameMatrixWrapper = GameMatrixWrapper::default();
     let mut player: Option<Player> = None;
     let multi_player: bool = args.multiplayer;
 
+    let profiles = args
+        .profiles
+        .iter()
+        .map(|name| {
+            cfg.profile(name).cloned().ok_or(Failure {
+                message: format!("No profile named \"{}\"", name),
+                trace: "Create it with \"profile new\" first".to_string(),
+                code: ResultCode::InvalidArgs,
+            })
+        })
+        .collect::<AppResult<Vec<Profile>>>()?;
+    if profiles.len() > if multi_player { 2 } else { 1 } {
+        return Err(Failure {
+            message: "Too many profiles".to_string(),
+            trace: "Play as one profile, or one for each side with --multiplayer".to_string(),
+            code: ResultCode::InvalidArgs,
+        });
+    }
+    if profiles.len() == 2 && profiles[0].name == profiles[1].name {
+        return Err(Failure {
+            message: "Both sides can't play as the same profile".to_string(),
+            trace: "".to_string(),
+            code: ResultCode::InvalidArgs,
+        });
+    }
+    let profile = profiles
+        .first()
+        .or(cfg.active_profile())
+        .cloned()
+        .unwrap_or(Profile {
+            name: cfg.user.clone().unwrap_or("User".to_string()),
+            ..Profile::default()
+        });
+
+    // Flags win over the profile's settings
+    let difficulty = args.difficulty.or(profile.difficulty).unwrap_or(NORMAL);
+    let algorithm = args
+        .algorithm
+        .or(profile.algorithm)
+        .unwrap_or(Algorithm::Minimax);
+    let style = args
+        .style
+        .or(profile.style);
+
     if !multi_player {
-        if args.player.as_ref().is_none() {
+        if args.player.as_ref().is_none() && args.random_player {
+            let players: Vec<Player> = Player::iter().collect();
+            player = helper.rng.with(|rng| players.choose(rng).copied());
+        } else if args.player.as_ref().is_none() {
             let welcome_message = format!(
                 "Welcome, {}. Please select a player: ",
-                cfg.user.as_deref().unwrap_or("User")
+                profile.name
             );
             match Select::new(&welcome_message, Player::iter().collect()).prompt() {
                 Ok(g) => {
@@ -64,25 +165,175 @@ pub fn handle(args: ArgsStart, cfg: &mut AppConfig, helper: &HelperService) -> T
         });
     }
 
-    let _result = gameloop(&mut game_matrix, player, multi_player, args.difficulty, helper)?;
-
-    match Confirm::new("Do you want to play again (Yes/No)? ").prompt() {
-        Ok(g) => {
-            match g {
-                true => {
-                    clear_terminal();
-                    return handle(args, cfg, helper);
-                },
-                false => {},
+    // Hot-seat players not picked with --as are asked for their names
+    let mut names: Vec<String> = profiles.iter().map(|g| g.name.clone()).collect();
+    if multi_player {
+        while names.len() < 2 {
+            let name = ask_name(names.len() + 1, cfg, helper)?;
+            if names.contains(&name) {
+                println!("{}", format!("{} is already playing", name).red());
+                continue;
             }
+            names.push(name);
+        }
+    }
+
+    let mut options = GameOptions {
+        player,
+        multi_player,
+        engine: EngineSettings {
+            difficulty,
+            algorithm,
+            style,
+            book: if args.no_book { None } else { Some(OpeningBook::load()?) },
+            iterations: args.iterations,
+            think_time: args.think_time,
+            threads: args.threads.map(usize::from),
+            menace: if algorithm == Algorithm::Menace {
+                Some(helper.get_menace()?)
+            } else {
+                None
+            },
+        },
+        external: match &args.engine_cmd {
+            Some(command) => Some(ExternalEngine::spawn(command, args.engine_timeout)?),
+            None => None,
+        },
+        coach: args.coach,
+        // A series is decided by its games, played without help
+        assists: args.best_of.is_none(),
+        names: None,
+        clock: None,
+        on_flag: args.on_flag,
+        series: None,
+    };
+
+    let mut series = MatchRecord {
+        date: date_string(today()),
+        players: match (&options.external, multi_player) {
+            (_, true) => [names[0].clone(), names[1].clone()],
+            (Some(engine), false) => [profile.name.clone(), engine.name.clone()],
+            (None, false) => [
+                profile.name.clone(),
+                format!("Computer {} ({})", difficulty, algorithm),
+            ],
         },
-        Err(_) => {},
+        best_of: args.best_of,
+        games: vec![],
+        seed: Some(helper.rng.seed()),
+    };
+    // Only games between profiles, or a profile and the built-in computer at a stock setting
+    // of its difficulty, without help, count towards ratings; guests aren't rated
+    let tuned = args.iterations.is_some()
+        || args.think_time.is_some()
+        || args.threads.is_some()
+        || args.no_book
+        || style.is_some();
+    let rated = !args.unrated
+        && !args.coach
+        && options.external.is_none()
+        && match multi_player {
+            true => names.iter().all(|g| cfg.profile(g).is_some()),
+            false => !tuned && cfg.profile(&profile.name).is_some(),
+        };
+    // Side of the first player, the person at the keyboard when playing the computer
+    let mut first_side = player.unwrap_or(Player::X);
+
+    loop {
+        let mut game_matrix = GameMatrixWrapper::default();
+        options.clock = Clock::new(args.move_time, args.clock);
+        if multi_player {
+            options.names = Some(if first_side == Player::X {
+                series.players.clone()
+            } else {
+                [series.players[1].clone(), series.players[0].clone()]
+            });
+        } else {
+            options.player = Some(first_side);
+        }
+        options.series = Some(series.clone());
+
+        let outcome = match gameloop(&mut game_matrix, &options, helper) {
+            Ok(g) => g,
+            Err(e) => {
+                if !series.games.is_empty() {
+                    helper.record_match(&series)?;
+                }
+                return Err(e);
+            }
+        };
+        // The first player may have flipped sides during the game
+        let side = outcome.player.unwrap_or(first_side);
+        series.games.push(MatchGame {
+            x: if side == Player::X { 0 } else { 1 },
+            winner: outcome.winner.map(|g| if g == side { 0 } else { 1 }),
+            cause: outcome.cause,
+        });
+        // MENACE has saved what it learned, the next game plays with it
+        if options.engine.menace.is_some() {
+            options.engine.menace = Some(helper.get_menace()?);
+        }
+
+        print_scoreboard(&series);
+        if rated && outcome.assisted {
+            println!("{}", "Not rated: hints, undo or flip were used".yellow());
+        } else if rated {
+            let score = match outcome.winner {
+                Some(g) if g == side => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            let ratings =
+                helper.rate_game(&series.players[0], &series.players[1], !multi_player, score)?;
+            if multi_player {
+                println!(
+                    "Ratings: {} {:.0} ({:+.0}), {} {:.0} ({:+.0})",
+                    series.players[0],
+                    ratings[0].0.rating,
+                    ratings[0].1,
+                    series.players[1],
+                    ratings[1].0.rating,
+                    ratings[1].1
+                );
+            } else {
+                println!("Rating: {:.0} ({:+.0})", ratings[0].0.rating, ratings[0].1);
+            }
+        }
+        let prompt = match series.best_of {
+            Some(_) if series.is_decided() => break,
+            Some(n) => {
+                first_side = first_side.invert();
+                format!(
+                    "Continue to game {} of {}, {} plays {} (Yes/No)? ",
+                    series.games.len() + 1,
+                    n,
+                    series.players[0],
+                    first_side
+                )
+            }
+            None => "Do you want to play again (Yes/No)? ".to_string(),
+        };
+        if !matches!(Confirm::new(&prompt).prompt(), Ok(true)) {
+            break;
+        }
+        clear_terminal();
     }
+    helper.record_match(&series)?;
 
     let analytics = helper.update_command_usage(ToolsAnalytics::START);
 
     Ok(Output {
-        message: None,
+        message: match (series.best_of, series.winner()) {
+            (Some(n), Some(g)) if series.is_decided() => Some(format!(
+                "{} wins the best of {}, {}-{}",
+                series.players[g],
+                n,
+                series.wins(g),
+                series.wins(1 - g)
+            )),
+            (Some(n), None) if series.is_decided() => Some(format!("The best of {} is drawn", n)),
+            _ => None,
+        },
         code: if analytics.is_ok() {
             ResultCode::Success
         } else {
@@ -90,3 +341,31 @@ pub fn handle(args: ArgsStart, cfg: &mut AppConfig, helper: &HelperService) -> T
         },
     })
 }
+
+/// Asks a hot-seat player for their name, suggesting the profiles on this machine.
+fn ask_name(number: usize, cfg: &AppConfig, helper: &HelperService) -> AppResult<String> {
+    let profiles: Vec<String> = cfg.profiles.iter().map(|g| g.name.clone()).collect();
+    let suggest = move |input: &str| -> Result<Vec<String>, CustomUserError> {
+        Ok(profiles
+            .iter()
+            .filter(|g| g.to_lowercase().starts_with(&input.to_lowercase()))
+            .cloned()
+            .collect())
+    };
+
+    let default = format!("Player {}", number);
+    let name = Text::new(&format!("Name of player {}:", number))
+        .with_default(&default)
+        .with_autocomplete(suggest)
+        .with_help_message("Pick a profile to have the game count towards it, or any name to play as a guest")
+        .prompt()
+        .map_err(|e| Failure {
+            message: "You have to enter a name".to_string(),
+            trace: format!("Reason: {}", helper.generate_inquire_error(e)),
+            code: ResultCode::InvalidArgs,
+        })?;
+    Ok(match name.trim() {
+        "" => default,
+        name => name.to_string(),
+    })
+}
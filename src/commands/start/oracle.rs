@@ -0,0 +1,143 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use super::{
+    actions::{DRAW, LOSE, WIN, ai_select, ai_unselect, evaluate_board, get_selectable, player_to_move},
+    computer::{count_nodes, generate_min_max_choices, parent_score},
+    game::{GameMatrix, Player},
+    symmetry::canonical_key,
+};
+
+static ORACLE: OnceLock<Oracle> = OnceLock::new();
+
+/// Game-theoretic values of every reachable, unfinished position, one entry per symmetry
+/// class. Values are for the side to move: `WIN - n` / `LOSE + n` for a win or loss `n`
+/// plies away, `DRAW` otherwise.
+pub struct Oracle {
+    table: HashMap<u32, i8>,
+}
+
+/// The oracle, solved on first use; a full solve visits well under a thousand positions.
+pub fn oracle() -> &'static Oracle {
+    ORACLE.get_or_init(Oracle::generate)
+}
+
+impl Oracle {
+    pub fn generate() -> Self {
+        let mut cache: HashMap<u32, i32> = HashMap::new();
+        generate_min_max_choices(&mut [[0; 3]; 3], Player::X, &mut cache);
+
+        Oracle {
+            table: cache.into_iter().map(|(k, v)| (k, v as i8)).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (u32, i32)> + '_ {
+        self.table.iter().map(|(k, v)| (*k, *v as i32))
+    }
+
+    /// Value of the position for the side to move, if it is reachable in normal play.
+    pub fn value(&self, gm: &GameMatrix) -> Option<i32> {
+        if let Some(score) = evaluate_board(gm, player_to_move(gm)) {
+            return Some(score);
+        }
+        self.table.get(&canonical_key(gm)).map(|g| *g as i32)
+    }
+
+    /// Plies left until the game ends under perfect play from both sides.
+    pub fn distance(&self, gm: &GameMatrix) -> Option<u16> {
+        let value = self.value(gm)?;
+        Some(match value {
+            DRAW => get_selectable(gm),
+            v if v > DRAW => (WIN - v) as u16,
+            v => (v - LOSE) as u16,
+        })
+    }
+
+    /// Same result as `search_moves_parallel` for the side to move, by table lookup.
+    pub fn score_moves(&self, gm: &GameMatrix) -> Option<Vec<(u16, i32)>> {
+        let player = player_to_move(gm);
+        let mut check_matrix = *gm;
+
+        let mut action_map: Vec<(u16, i32)> = vec![];
+        for i in 1..=get_selectable(gm) {
+            let pos = ai_select(&mut check_matrix, i, player)?;
+            let value = self.value(&check_matrix);
+            ai_unselect(&mut check_matrix, pos);
+            action_map.push((i, parent_score(value?)));
+        }
+        count_nodes(action_map.len() as u64);
+
+        action_map.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        Some(action_map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::oracle;
+    use crate::commands::start::{
+        actions::{ai_select, check_win_slim, get_selectable, player_to_move, DRAW, LOSE},
+        computer::{default_threads, search_moves_parallel},
+        game::{GameMatrix, Player},
+    };
+
+    /// Plain minimax with no cache or symmetry, as an independent reference.
+    fn reference_value(gm: &GameMatrix, player: Player) -> i32 {
+        if check_win_slim(gm).is_some() {
+            return LOSE;
+        }
+        if get_selectable(gm) == 0 {
+            return DRAW;
+        }
+        (1..=get_selectable(gm))
+            .map(|i| {
+                let mut next = *gm;
+                ai_select(&mut next, i, player);
+                let score = -reference_value(&next, player.invert());
+                score - score.signum()
+            })
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_oracle_covers_reachable_positions() {
+        assert!(oracle().len() > 500 && oracle().len() < 6000);
+        assert_eq!(oracle().value(&[[0; 3]; 3]), Some(DRAW));
+        assert_eq!(oracle().distance(&[[0; 3]; 3]), Some(9));
+    }
+
+    #[test]
+    fn test_search_matches_oracle() {
+        let mut checked = 0;
+        let mut boards: Vec<GameMatrix> = vec![[[0; 3]; 3]];
+        while let Some(gm) = boards.pop() {
+            if check_win_slim(&gm).is_some() || get_selectable(&gm) == 0 {
+                continue;
+            }
+            let player = player_to_move(&gm);
+            assert_eq!(oracle().score_moves(&gm), Some(search_moves_parallel(&gm, player, default_threads())));
+            if checked < 20 {
+                assert_eq!(search_moves_parallel(&gm, player, 1), search_moves_parallel(&gm, player, 3));
+            }
+            if get_selectable(&gm) <= 6 {
+                assert_eq!(oracle().value(&gm), Some(reference_value(&gm, player)));
+            }
+            checked += 1;
+
+            // Three plies deep covers every opening; beyond that a sample of lines is enough
+            if get_selectable(&gm) > 6 || checked % 7 == 0 {
+                for i in 1..=get_selectable(&gm) {
+                    let mut next = gm;
+                    ai_select(&mut next, i, player);
+                    boards.push(next);
+                }
+            }
+        }
+        assert!(checked > 500);
+    }
+}
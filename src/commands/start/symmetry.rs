@@ -0,0 +1,92 @@
+use super::game::GameMatrix;
+
+/// The 8 symmetries of the board: 4 rotations, each optionally preceded by a mirror.
+pub const SYMMETRIES: usize = 8;
+
+/// Where `cell` lands under symmetry `sym`.
+pub fn transform_cell(cell: (usize, usize), sym: usize) -> (usize, usize) {
+    let (mut row, mut col) = cell;
+    if sym >= 4 {
+        col = 2 - col;
+    }
+    for _ in 0..sym % 4 {
+        (row, col) = (col, 2 - row);
+    }
+    (row, col)
+}
+
+/// The cell that symmetry `sym` moves onto `cell`, undoing `transform_cell`.
+pub fn untransform_cell(cell: (usize, usize), sym: usize) -> (usize, usize) {
+    (0..9)
+        .map(|g| (g / 3, g % 3))
+        .find(|&g| transform_cell(g, sym) == cell)
+        .unwrap_or(cell)
+}
+
+pub fn transform(gm: &GameMatrix, sym: usize) -> GameMatrix {
+    let mut out: GameMatrix = [[0; 3]; 3];
+    for (i, row) in gm.iter().enumerate() {
+        for (j, &cell) in row.iter().enumerate() {
+            let (r, c) = transform_cell((i, j), sym);
+            out[r][c] = cell;
+        }
+    }
+    out
+}
+
+/// Base-3 encoding of the board, also used as the canonical ordering.
+pub fn board_key(gm: &GameMatrix) -> u32 {
+    gm.iter().flatten().fold(0, |acc, &g| acc * 3 + g as u32)
+}
+
+pub fn board_from_key(key: u32) -> GameMatrix {
+    let mut gm: GameMatrix = [[0; 3]; 3];
+    let mut key = key;
+    for idx in (0..9).rev() {
+        gm[idx / 3][idx % 3] = (key % 3) as i32;
+        key /= 3;
+    }
+    gm
+}
+
+/// The representative of the board's symmetry class, with the symmetry that produces it.
+pub fn canonicalize(gm: &GameMatrix) -> (GameMatrix, usize) {
+    (0..SYMMETRIES)
+        .map(|sym| (transform(gm, sym), sym))
+        .min_by_key(|g| board_key(&g.0))
+        .unwrap()
+}
+
+pub fn canonical_key(gm: &GameMatrix) -> u32 {
+    board_key(&canonicalize(gm).0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        SYMMETRIES, board_from_key, board_key, canonical_key, canonicalize, transform,
+        transform_cell, untransform_cell,
+    };
+
+    #[test]
+    fn test_symmetric_boards_share_a_key() {
+        let gm = [[1, 0, 0], [0, 2, 0], [0, 0, 0]];
+        for sym in 0..SYMMETRIES {
+            assert_eq!(canonical_key(&transform(&gm, sym)), canonical_key(&gm));
+        }
+        assert_ne!(canonical_key(&gm), canonical_key(&[[0, 1, 0], [0, 2, 0], [0, 0, 0]]));
+        assert_eq!(board_from_key(board_key(&gm)), gm);
+    }
+
+    #[test]
+    fn test_moves_map_back_to_original_orientation() {
+        let gm = [[0, 0, 1], [0, 0, 0], [2, 0, 0]];
+        let (canonical, sym) = canonicalize(&gm);
+        for i in 0..9 {
+            let cell = (i / 3, i % 3);
+            let original = untransform_cell(transform_cell(cell, sym), sym);
+            assert_eq!(original, cell);
+            assert_eq!(canonical[transform_cell(cell, sym).0][transform_cell(cell, sym).1], gm[i / 3][i % 3]);
+        }
+    }
+}
@@ -0,0 +1,448 @@
+use std::time::Duration;
+
+use clap::{Args, Subcommand, ValueEnum};
+use colored::Colorize;
+use comfy_table::{Cell, Color, Table};
+use inquire::Confirm;
+use schedule::{is_finished, knockout_rounds, round_robin, schedule, standings};
+
+use crate::{
+    AppResult, Failure, Output, ResultCode, ToolResult,
+    commands::start::{
+        actions::{check_win, check_win_slim, get_selectable, print_final_game_matrix},
+        book::OpeningBook,
+        clock::OnFlag,
+        computer::{Algorithm, EngineSettings, make_move},
+        external::ExternalEngine,
+        game::{GameMatrixWrapper, GameOptions, Player, gameloop},
+    },
+    services::{
+        config::AppConfig,
+        db::records::{
+            GameResult, Participant, ParticipantKind, ToolsAnalytics, Tournament,
+            TournamentFormat,
+        },
+        helper::HelperService,
+    },
+};
+
+pub mod schedule;
+
+const ENGINE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Args)]
+#[command(about = ABOUT_TOURNAMENT, long_about = LONG_ABOUT_TOURNAMENT)]
+pub struct ArgsTournament {
+    #[command(subcommand)]
+    action: TournamentAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum TournamentAction {
+    #[command(about = "Register participants and schedule a new tournament")]
+    New {
+        name: String,
+        #[arg(short, long, default_value = "round-robin", help = "Tournament format")]
+        format: TournamentFormat,
+        #[arg(
+            short,
+            long = "player",
+            required = true,
+            help = "Participants in seeding order: human[:name], computer:<difficulty>[:algorithm] or engine:<command>"
+        )]
+        players: Vec<String>,
+    },
+    #[command(about = "Play the remaining games, stopping whenever you like")]
+    Play {
+        #[arg(help = "Tournament to play; the latest unfinished one when not given")]
+        name: Option<String>,
+    },
+    #[command(about = "Show standings and results")]
+    Standings {
+        #[arg(help = "Tournament to show; the latest one when not given")]
+        name: Option<String>,
+    },
+    #[command(about = "List saved tournaments")]
+    List,
+    #[command(about = "Delete a tournament")]
+    Delete { name: String },
+}
+
+const ABOUT_TOURNAMENT: &'static str = "Run a tournament";
+const LONG_ABOUT_TOURNAMENT: &'static str = "Run a round robin or knockout tournament between people, computer difficulties and external engines. Every pairing plays a game with each side moving first, and results are saved as they come in so a tournament can be resumed later.";
+
+pub fn handle(args: ArgsTournament, cfg: &mut AppConfig, helper: &HelperService) -> ToolResult {
+    let message = match args.action {
+        TournamentAction::New {
+            name,
+            format,
+            players,
+        } => {
+            if helper.get_tournaments()?.tournaments.iter().any(|g| g.name == name) {
+                return Err(Failure {
+                    message: format!("A tournament named \"{}\" already exists", name),
+                    trace: "Choose another name or delete the old tournament first".to_string(),
+                    code: ResultCode::InvalidArgs,
+                });
+            }
+
+            if players.len() < 2 {
+                return Err(Failure {
+                    message: "A tournament needs at least two participants".to_string(),
+                    trace: "Add each one with -p".to_string(),
+                    code: ResultCode::InvalidArgs,
+                });
+            }
+            let participants = players
+                .iter()
+                .map(|g| parse_participant(g, cfg))
+                .collect::<AppResult<Vec<Participant>>>()?;
+            let mut tournament = Tournament {
+                name,
+                format,
+                games: match format {
+                    TournamentFormat::RoundRobin => round_robin(participants.len()),
+                    TournamentFormat::Knockout => vec![],
+                },
+                participants,
+            };
+            schedule(&mut tournament);
+            helper.save_tournament(&tournament)?;
+
+            print_standings(&tournament);
+            Some(format!(
+                "Tournament \"{}\" created, start it with \"tournament play\"",
+                tournament.name
+            ))
+        }
+        TournamentAction::Play { name } => {
+            let mut tournament = find_tournament(helper, name.as_deref(), true)?;
+            play(&mut tournament, helper)?;
+            print_standings(&tournament);
+            None
+        }
+        TournamentAction::Standings { name } => {
+            let tournament = find_tournament(helper, name.as_deref(), false)?;
+            print_standings(&tournament);
+            None
+        }
+        TournamentAction::List => {
+            let mut table = Table::new();
+            table.set_header(vec!["Name", "Format", "Participants", "Played", "Status"]);
+            for g in helper.get_tournaments()?.tournaments.iter() {
+                table.add_row(vec![
+                    Cell::new(&g.name),
+                    Cell::new(format_name(g.format)),
+                    Cell::new(g.participants.len()),
+                    Cell::new(format!(
+                        "{}/{}",
+                        g.games.iter().filter(|g| g.result.is_some()).count(),
+                        g.games.len()
+                    )),
+                    match is_finished(g) {
+                        true => Cell::new("Finished").fg(Color::Green),
+                        false => Cell::new("In progress").fg(Color::Yellow),
+                    },
+                ]);
+            }
+            println!("{}", table);
+            None
+        }
+        TournamentAction::Delete { name } => {
+            if !helper.delete_tournament(&name)? {
+                return Err(Failure {
+                    message: format!("No tournament named \"{}\"", name),
+                    trace: "Use \"tournament list\" to see saved tournaments".to_string(),
+                    code: ResultCode::InvalidArgs,
+                });
+            }
+            Some(format!("Tournament \"{}\" deleted", name))
+        }
+    };
+
+    let analytics = helper.update_command_usage(ToolsAnalytics::TOURNAMENT);
+
+    Ok(Output {
+        message,
+        code: if analytics.is_ok() {
+            ResultCode::Success
+        } else {
+            ResultCode::SuccessAnalyticsFailed
+        },
+    })
+}
+
+fn parse_participant(spec: &str, cfg: &AppConfig) -> AppResult<Participant> {
+    let invalid = |reason: &str| Failure {
+        message: format!("Invalid participant \"{}\"", spec),
+        trace: format!("Reason: {}", reason),
+        code: ResultCode::InvalidArgs,
+    };
+
+    let (kind, rest) = spec.split_once(':').unwrap_or((spec, ""));
+    match kind.to_lowercase().as_str() {
+        "human" => Ok(Participant {
+            name: match rest.trim() {
+                "" => cfg.user.clone().unwrap_or("User".to_string()),
+                name => name.to_string(),
+            },
+            kind: ParticipantKind::Human,
+        }),
+        "computer" => {
+            let (difficulty, algorithm) = rest.split_once(':').unwrap_or((rest, "minimax"));
+            let difficulty: u8 = difficulty
+                .parse()
+                .ok()
+                .filter(|g| (1..=4).contains(g))
+                .ok_or(invalid("difficulty must be 1-4"))?;
+            let algorithm = Algorithm::from_str(algorithm, true)
+                .map_err(|_| invalid(&format!("unknown algorithm \"{}\"", algorithm)))?;
+            Ok(Participant {
+                name: format!("Computer {} ({})", difficulty, algorithm),
+                kind: ParticipantKind::Computer {
+                    difficulty,
+                    algorithm: algorithm.to_string(),
+                },
+            })
+        }
+        "engine" if !rest.trim().is_empty() => Ok(Participant {
+            name: rest.trim().to_string(),
+            kind: ParticipantKind::Engine {
+                command: rest.trim().to_string(),
+            },
+        }),
+        _ => Err(invalid(
+            "use human[:name], computer:<difficulty>[:algorithm] or engine:<command>",
+        )),
+    }
+}
+
+fn format_name(format: TournamentFormat) -> &'static str {
+    match format {
+        TournamentFormat::RoundRobin => "Round robin",
+        TournamentFormat::Knockout => "Knockout",
+    }
+}
+
+fn find_tournament(
+    helper: &HelperService,
+    name: Option<&str>,
+    unfinished: bool,
+) -> AppResult<Tournament> {
+    let record = helper.get_tournaments()?;
+    let found = match name {
+        Some(name) => record.tournaments.into_iter().find(|g| g.name == name),
+        None => record
+            .tournaments
+            .into_iter()
+            .rev()
+            .find(|g| !unfinished || !is_finished(g)),
+    };
+    found.ok_or(Failure {
+        message: match name {
+            Some(name) => format!("No tournament named \"{}\"", name),
+            None => "No tournament to play".to_string(),
+        },
+        trace: "Create one with \"tournament new\"".to_string(),
+        code: ResultCode::InvalidArgs,
+    })
+}
+
+/// Plays unplayed games in order, saving after each one. Games with a person in them are
+/// confirmed first, declining stops for now.
+fn play(tournament: &mut Tournament, helper: &HelperService) -> AppResult<()> {
+    loop {
+        schedule(tournament);
+        let Some(idx) = tournament.games.iter().position(|g| g.result.is_none()) else {
+            println!("{}", "The tournament is over.".bold().green());
+            return Ok(());
+        };
+
+        let game = tournament.games[idx].clone();
+        let x = &tournament.participants[game.x];
+        let o = &tournament.participants[game.o];
+        println!(
+            "\n{}",
+            format!("Round {}: {} (X) vs {} (O)", game.round, x.name, o.name).bold()
+        );
+
+        let human = x.kind == ParticipantKind::Human || o.kind == ParticipantKind::Human;
+        if human
+            && !Confirm::new("Play this game now (Yes/No)? ")
+                .with_default(true)
+                .prompt()
+                .map_err(|e| Failure {
+                    message: "Invalid input".to_string(),
+                    trace: format!("Reason: {}", helper.generate_inquire_error(e)),
+                    code: ResultCode::CancelOperation,
+                })?
+        {
+            return Ok(());
+        }
+
+        let winner = play_game(x, o, helper)?;
+        tournament.games[idx].result = Some(match winner {
+            Some(Player::X) => GameResult::Win(game.x),
+            Some(Player::O) => GameResult::Win(game.o),
+            None => GameResult::Draw,
+        });
+        helper.save_tournament(tournament)?;
+    }
+}
+
+fn engine_settings(kind: &ParticipantKind, helper: &HelperService) -> AppResult<EngineSettings> {
+    let mut engine = EngineSettings {
+        book: Some(OpeningBook::load()?),
+        ..EngineSettings::default()
+    };
+    if let ParticipantKind::Computer {
+        difficulty,
+        algorithm,
+    } = kind
+    {
+        engine.difficulty = *difficulty;
+        engine.algorithm = Algorithm::from_str(algorithm, true).unwrap_or(Algorithm::Minimax);
+        if engine.algorithm == Algorithm::Menace {
+            engine.menace = Some(helper.get_menace()?);
+        }
+    }
+    Ok(engine)
+}
+
+fn external_engine(kind: &ParticipantKind) -> AppResult<Option<ExternalEngine>> {
+    match kind {
+        ParticipantKind::Engine { command } => {
+            Ok(Some(ExternalEngine::spawn(command, ENGINE_TIMEOUT)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn play_game(
+    x: &Participant,
+    o: &Participant,
+    helper: &HelperService,
+) -> AppResult<Option<Player>> {
+    let mut game_matrix = GameMatrixWrapper::default();
+
+    let human = match (&x.kind, &o.kind) {
+        (ParticipantKind::Human, ParticipantKind::Human) => None,
+        (ParticipantKind::Human, _) => Some(Player::X),
+        (_, ParticipantKind::Human) => Some(Player::O),
+        _ => return play_automatic(x, o, helper),
+    };
+    let opponent = match human {
+        Some(Player::O) => x,
+        _ => o,
+    };
+
+    let options = GameOptions {
+        player: human,
+        multi_player: human.is_none(),
+        engine: engine_settings(&opponent.kind, helper)?,
+        external: external_engine(&opponent.kind)?,
+        coach: false,
+        assists: false,
+        names: human.is_none().then(|| [x.name.clone(), o.name.clone()]),
+        clock: None,
+        on_flag: OnFlag::Lose,
+        series: None,
+    };
+    Ok(gameloop(&mut game_matrix, &options, helper)?.winner)
+}
+
+/// Plays a game between two programs, an engine that fails to move forfeits.
+fn play_automatic(
+    x: &Participant,
+    o: &Participant,
+    helper: &HelperService,
+) -> AppResult<Option<Player>> {
+    let engines = [engine_settings(&x.kind, helper)?, engine_settings(&o.kind, helper)?];
+    let externals = [external_engine(&x.kind)?, external_engine(&o.kind)?];
+
+    let mut game_matrix = GameMatrixWrapper::default();
+    let mut player = Player::X;
+    let mut forfeit: Option<Player> = None;
+    while check_win_slim(&game_matrix.value()).is_none()
+        && get_selectable(&game_matrix.value()) > 0
+    {
+        let side = if player == Player::X { 0 } else { 1 };
+        match &externals[side] {
+            Some(engine) => match engine.request_move(&game_matrix.value(), player) {
+                Ok(cell) => game_matrix.set_position(cell, player),
+                Err(reason) => {
+                    println!("{}", format!("{} forfeits: {}", engine.name, reason).red());
+                    forfeit = Some(player);
+                    break;
+                }
+            },
+            None => {
+                helper
+                    .rng
+                    .with(|rng| make_move(&mut game_matrix, player, &engines[side], rng));
+            }
+        }
+        player = player.invert();
+    }
+
+    let (winner, win_cells) = match forfeit {
+        Some(g) => (Some(g.invert()), vec![]),
+        None => check_win(&mut game_matrix)?,
+    };
+    print_final_game_matrix(&game_matrix, win_cells);
+    match winner {
+        Some(g) => println!("{} wins", if g == Player::X { &x.name } else { &o.name }),
+        None => println!("Draw"),
+    }
+    for engine in externals.iter().flatten() {
+        engine.send_result(winner);
+    }
+    Ok(winner)
+}
+
+fn print_standings(tournament: &Tournament) {
+    println!(
+        "\n{}",
+        format!("{} ({})", tournament.name, format_name(tournament.format)).bold()
+    );
+
+    let mut table = Table::new();
+    table.set_header(vec!["#", "Participant", "Played", "Won", "Drawn", "Lost", "Points"]);
+    for (rank, g) in standings(tournament).iter().enumerate() {
+        table.add_row(vec![
+            Cell::new(rank + 1),
+            Cell::new(&tournament.participants[g.participant].name),
+            Cell::new(g.played),
+            Cell::new(g.wins),
+            Cell::new(g.draws),
+            Cell::new(g.losses),
+            Cell::new(g.points()),
+        ]);
+    }
+    println!("{}", table);
+
+    if tournament.format == TournamentFormat::Knockout {
+        let name = |slot: Option<usize>| match slot {
+            Some(g) => tournament.participants[g].name.clone(),
+            None => "bye".to_string(),
+        };
+        for (round, slots) in knockout_rounds(tournament).iter().enumerate() {
+            if slots.len() == 1 {
+                println!("{} {}", "Champion:".bold().green(), name(slots[0]).green());
+            } else {
+                let pairs: Vec<String> = slots
+                    .chunks(2)
+                    .map(|g| format!("{} vs {}", name(g[0]), name(g[1])))
+                    .collect();
+                println!("Round {}: {}", round + 1, pairs.join(", "));
+            }
+        }
+    } else if is_finished(tournament) {
+        let winner = standings(tournament)[0].participant;
+        println!(
+            "{} {}",
+            "Winner:".bold().green(),
+            tournament.participants[winner].name.green()
+        );
+    }
+}
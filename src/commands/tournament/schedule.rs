@@ -0,0 +1,243 @@
+use crate::services::db::records::{GameResult, Tournament, TournamentFormat, TournamentGame};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Standing {
+    pub participant: usize,
+    pub played: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Standing {
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+}
+
+/// Both legs of a round robin, each pairing meeting once with either side moving first.
+/// Rounds follow the circle method so everyone plays once per round.
+pub fn round_robin(count: usize) -> Vec<TournamentGame> {
+    // An odd field gets a dummy entrant, whoever meets it sits the round out
+    let mut seats: Vec<Option<usize>> = (0..count).map(Some).collect();
+    if count % 2 == 1 {
+        seats.push(None);
+    }
+    let rounds = seats.len().saturating_sub(1);
+
+    let mut first_leg: Vec<TournamentGame> = vec![];
+    for round in 0..rounds {
+        for i in 0..seats.len() / 2 {
+            if let (Some(a), Some(b)) = (seats[i], seats[seats.len() - 1 - i]) {
+                let (x, o) = if (round + i) % 2 == 0 { (a, b) } else { (b, a) };
+                first_leg.push(TournamentGame {
+                    round: round as u32 + 1,
+                    x,
+                    o,
+                    result: None,
+                });
+            }
+        }
+        seats[1..].rotate_right(1);
+    }
+
+    let second_leg: Vec<TournamentGame> = first_leg
+        .iter()
+        .map(|g| TournamentGame {
+            round: g.round + rounds as u32,
+            x: g.o,
+            o: g.x,
+            result: None,
+        })
+        .collect();
+    first_leg.into_iter().chain(second_leg).collect()
+}
+
+/// Bracket positions for `size` seeds, 0 being the top seed, so the top two can only meet
+/// in the final.
+fn seed_order(size: usize) -> Vec<usize> {
+    let mut order = vec![0];
+    while order.len() < size {
+        let n = order.len() * 2;
+        order = order.iter().flat_map(|&s| [s, n - 1 - s]).collect();
+    }
+    order
+}
+
+/// Winner of a knockout match, once both its games are played. A level match goes to the
+/// higher seed.
+fn match_winner(tournament: &Tournament, round: u32, a: usize, b: usize) -> Option<usize> {
+    let games: Vec<&TournamentGame> = tournament
+        .games
+        .iter()
+        .filter(|g| g.round == round && ((g.x == a && g.o == b) || (g.x == b && g.o == a)))
+        .collect();
+    if games.is_empty() || games.iter().any(|g| g.result.is_none()) {
+        return None;
+    }
+
+    let wins = |p: usize| games.iter().filter(|g| g.result == Some(GameResult::Win(p))).count();
+    Some(match wins(a).cmp(&wins(b)) {
+        std::cmp::Ordering::Greater => a,
+        std::cmp::Ordering::Less => b,
+        std::cmp::Ordering::Equal => a.min(b),
+    })
+}
+
+/// Bracket slots of every round decided so far plus the one being played; `None` is a bye.
+pub fn knockout_rounds(tournament: &Tournament) -> Vec<Vec<Option<usize>>> {
+    let count = tournament.participants.len();
+    let mut rounds: Vec<Vec<Option<usize>>> = vec![
+        seed_order(count.next_power_of_two())
+            .into_iter()
+            .map(|s| (s < count).then_some(s))
+            .collect(),
+    ];
+
+    while rounds.last().unwrap().len() > 1 {
+        let round = rounds.len() as u32;
+        let mut next = vec![];
+        for pair in rounds.last().unwrap().chunks(2) {
+            next.push(match (pair[0], pair[1]) {
+                (Some(a), Some(b)) => match match_winner(tournament, round, a, b) {
+                    Some(g) => Some(g),
+                    None => return rounds,
+                },
+                (a, b) => a.or(b),
+            });
+        }
+        rounds.push(next);
+    }
+    rounds
+}
+
+/// Adds the games of the next knockout round once the previous one is decided.
+pub fn schedule(tournament: &mut Tournament) {
+    if tournament.format != TournamentFormat::Knockout {
+        return;
+    }
+
+    let rounds = knockout_rounds(tournament);
+    let round = rounds.len() as u32;
+    let slots = rounds.last().unwrap();
+    if slots.len() <= 1 || tournament.games.iter().any(|g| g.round == round) {
+        return;
+    }
+    for pair in slots.chunks(2) {
+        if let (Some(a), Some(b)) = (pair[0], pair[1]) {
+            for (x, o) in [(a, b), (b, a)] {
+                tournament.games.push(TournamentGame {
+                    round,
+                    x,
+                    o,
+                    result: None,
+                });
+            }
+        }
+    }
+}
+
+pub fn is_finished(tournament: &Tournament) -> bool {
+    match tournament.format {
+        TournamentFormat::RoundRobin => tournament.games.iter().all(|g| g.result.is_some()),
+        TournamentFormat::Knockout => knockout_rounds(tournament).last().unwrap().len() == 1,
+    }
+}
+
+/// Points table, best first; ties are ordered by wins, then by seed.
+pub fn standings(tournament: &Tournament) -> Vec<Standing> {
+    let mut table: Vec<Standing> = (0..tournament.participants.len())
+        .map(|participant| Standing {
+            participant,
+            ..Standing::default()
+        })
+        .collect();
+
+    for game in tournament.games.iter() {
+        let Some(result) = game.result else {
+            continue;
+        };
+        for side in [game.x, game.o] {
+            let standing = &mut table[side];
+            standing.played += 1;
+            match result {
+                GameResult::Draw => standing.draws += 1,
+                GameResult::Win(g) if g == side => standing.wins += 1,
+                GameResult::Win(_) => standing.losses += 1,
+            }
+        }
+    }
+
+    table.sort_by(|a, b| {
+        b.points()
+            .total_cmp(&a.points())
+            .then(b.wins.cmp(&a.wins))
+            .then(a.participant.cmp(&b.participant))
+    });
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_finished, knockout_rounds, round_robin, schedule, seed_order};
+    use crate::services::db::records::{
+        GameResult, Participant, ParticipantKind, Tournament, TournamentFormat,
+    };
+
+    fn tournament(count: usize, format: TournamentFormat) -> Tournament {
+        Tournament {
+            name: "test".to_string(),
+            format,
+            participants: (0..count)
+                .map(|g| Participant {
+                    name: format!("p{}", g),
+                    kind: ParticipantKind::Human,
+                })
+                .collect(),
+            games: match format {
+                TournamentFormat::RoundRobin => round_robin(count),
+                TournamentFormat::Knockout => vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn test_round_robin_pairs_everyone_twice() {
+        for count in 2..=7 {
+            let games = round_robin(count);
+            assert_eq!(games.len(), count * (count - 1));
+            for a in 0..count {
+                for b in 0..count {
+                    let met = games.iter().filter(|g| g.x == a && g.o == b).count();
+                    assert_eq!(met, (a != b) as usize, "{} players, {} vs {}", count, a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_knockout_bracket() {
+        assert_eq!(seed_order(8), vec![0, 7, 3, 4, 1, 6, 2, 5]);
+
+        // Five players: the top three seeds get byes, 3 meets 4 in the first round
+        let mut t = tournament(5, TournamentFormat::Knockout);
+        schedule(&mut t);
+        assert_eq!(t.games.len(), 2);
+        assert!(t.games.iter().all(|g| g.round == 1 && g.x + g.o == 7));
+
+        // Each side wins one game, so the higher seed goes through
+        t.games[0].result = Some(GameResult::Win(t.games[0].x));
+        t.games[1].result = Some(GameResult::Win(t.games[1].x));
+        schedule(&mut t);
+        assert_eq!(knockout_rounds(&t)[1], vec![Some(0), Some(3), Some(1), Some(2)]);
+        assert_eq!(t.games.len(), 6);
+
+        while !is_finished(&t) {
+            for g in t.games.iter_mut().filter(|g| g.result.is_none()) {
+                g.result = Some(GameResult::Draw);
+            }
+            schedule(&mut t);
+        }
+        assert_eq!(knockout_rounds(&t).last().unwrap(), &vec![Some(0)]);
+    }
+}
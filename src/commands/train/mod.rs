@@ -0,0 +1,149 @@
+use std::{fs, path::PathBuf};
+
+use clap::{Args, ValueEnum};
+use comfy_table::Table;
+use indicatif::ProgressBar;
+use rand::Rng;
+use strum_macros::Display;
+
+use crate::{
+    Failure, Output, ResultCode, ToolResult,
+    commands::start::{
+        actions::{ai_select, check_win_slim, get_selectable},
+        computer::{default_threads, rank_moves},
+        game::{GameMatrix, Player},
+        menace::{learn, menace_move},
+    },
+    services::{
+        config::AppConfig,
+        db::records::{MenaceRecord, ToolsAnalytics},
+        helper::HelperService,
+    },
+};
+
+#[derive(Debug, Args)]
+#[command(about = ABOUT_TRAIN, long_about = LONG_ABOUT_TRAIN)]
+pub struct ArgsTrain {
+    #[arg(short, long, help = "Number of training games to play")]
+    games: Option<u32>,
+    #[arg(short, long, default_value = "menace", help = "Who MENACE trains against")]
+    opponent: Opponent,
+    #[arg(long, help = "Write the learned matchboxes to a JSON file")]
+    export: Option<PathBuf>,
+    #[arg(long, action = clap::ArgAction::SetTrue, help = "Forget everything MENACE has learned")]
+    reset: bool,
+}
+
+#[derive(ValueEnum, Display, Debug, Clone, Copy, PartialEq)]
+enum Opponent {
+    Menace,
+    Random,
+    Perfect,
+}
+
+const ABOUT_TRAIN: &'static str = "Train the learning computer player";
+const LONG_ABOUT_TRAIN: &'static str = "Train MENACE, the computer player that learns from its games (\"start -a menace\"). It keeps beads for every move in every position, adds some after a win or draw and takes one away after a loss.";
+
+pub fn handle(args: ArgsTrain, _cfg: &mut AppConfig, helper: &HelperService) -> ToolResult {
+    if args.reset {
+        helper.reset_menace()?;
+        return Ok(Output {
+            message: Some("MENACE has forgotten everything it learned".to_string()),
+            code: ResultCode::Success,
+        });
+    }
+
+    let mut record = helper.get_menace()?;
+
+    if let Some(games) = args.games {
+        let progress = ProgressBar::new(games as u64);
+        helper.rng.with(|rng| {
+            for n in 0..games {
+                // MENACE takes turns playing first against other opponents
+                let side = if n % 2 == 0 { Player::X } else { Player::O };
+                let (history, winner) = play_training_game(&record, side, args.opponent, rng);
+                match args.opponent {
+                    Opponent::Menace => {
+                        learn(&mut record, &history, Player::X, winner);
+                        learn(&mut record, &history, Player::O, winner);
+                        record.games += 1;
+                    }
+                    _ => {
+                        learn(&mut record, &history, side, winner);
+                        record.tally(winner.map(|g| g == side));
+                    }
+                }
+                progress.inc(1);
+            }
+        });
+        progress.finish_and_clear();
+        helper.save_menace(&record)?;
+    }
+
+    if let Some(path) = args.export.as_ref() {
+        let data = serde_json::to_string_pretty(&record).map_err(|e| Failure {
+            message: "Failed to serialize MENACE".to_string(),
+            trace: format!("Reason: {}", e),
+            code: ResultCode::SerializationError,
+        })?;
+        fs::write(path, data).map_err(|e| Failure {
+            message: "Failed to export matchboxes".to_string(),
+            trace: format!("Reason: {}", e),
+            code: ResultCode::PathError,
+        })?;
+        println!("Matchboxes written to {}", path.display());
+    }
+
+    let mut table = Table::new();
+    table.set_header(vec!["Matchboxes", "Games", "Won", "Drawn", "Lost"]);
+    table.add_row(vec![
+        record.boxes.len().to_string(),
+        record.games.to_string(),
+        record.wins.to_string(),
+        record.draws.to_string(),
+        record.losses.to_string(),
+    ]);
+    println!("{}", table);
+    println!("Seed: {}", helper.rng.seed());
+
+    let analytics = helper.update_command_usage(ToolsAnalytics::TRAIN);
+
+    Ok(Output {
+        message: args.games.map(|g| format!("MENACE trained on {} game(s)", g)),
+        code: if analytics.is_ok() {
+            ResultCode::Success
+        } else {
+            ResultCode::SuccessAnalyticsFailed
+        },
+    })
+}
+
+/// Plays one game with MENACE on `side`, or on both sides against itself.
+fn play_training_game<R: Rng>(
+    record: &MenaceRecord,
+    side: Player,
+    opponent: Opponent,
+    rng: &mut R,
+) -> (Vec<(usize, usize)>, Option<Player>) {
+    let mut gm: GameMatrix = [[0; 3]; 3];
+    let mut history = vec![];
+    let mut player = Player::X;
+
+    while check_win_slim(&gm).is_none() && get_selectable(&gm) > 0 {
+        let cell = if player == side || opponent == Opponent::Menace {
+            menace_move(record, &gm, rng)
+        } else {
+            let position = match opponent {
+                Opponent::Perfect => rank_moves(&gm, player, None, default_threads(), rng)[0].0,
+                _ => rng.random_range(1..=get_selectable(&gm)),
+            };
+            ai_select(&mut gm.clone(), position, player)
+        }
+        .unwrap();
+        gm[cell.0][cell.1] = player.as_i32();
+        history.push(cell);
+        player = player.invert();
+    }
+
+    (history, check_win_slim(&gm).and_then(Player::from_i32))
+}
@@ -2,7 +2,7 @@ use clap::{Parser, Subcommand, command};
 use colored::Colorize;
 use commands::start::ArgsStart;
 use tictactoe::{
-    commands::{self, init::{self, ArgsInit}, start}, services::{config::{codes::ResultCode, AppConfig}, crypt::CryptService, db::DBService, helper::HelperService}, Failure, Output, APP_NAME, CONFIG_NAME
+    commands::{self, analyze::{self, ArgsAnalyze}, arena::{self, ArgsArena}, book::{self, ArgsBook}, daily::{self, ArgsDaily}, explore::{self, ArgsExplore}, init::{self, ArgsInit}, profile::{self, ArgsProfile}, puzzle::{self, ArgsPuzzle}, ratings::{self, ArgsRatings}, solve::{self, ArgsSolve}, start, tournament::{self, ArgsTournament}, train::{self, ArgsTrain}}, services::{config::{codes::ResultCode, AppConfig}, crypt::CryptService, db::DBService, helper::HelperService, rng::RngService}, Failure, Output, APP_NAME, CONFIG_NAME
 };
 
 #[derive(Parser)]
@@ -10,26 +10,51 @@ use tictactoe::{
 struct App {
     #[command(subcommand)]
     command: Command,
+    #[arg(long, global = true, help = "Seed for every random choice, to replay a session exactly")]
+    seed: Option<u64>,
 }
 
 #[derive(Subcommand)]
 enum Command {
     INIT(ArgsInit),
     START(ArgsStart),
+    ANALYZE(ArgsAnalyze),
+    PUZZLE(ArgsPuzzle),
+    DAILY(ArgsDaily),
+    BOOK(ArgsBook),
+    SOLVE(ArgsSolve),
+    TRAIN(ArgsTrain),
+    ARENA(ArgsArena),
+    EXPLORE(ArgsExplore),
+    TOURNAMENT(ArgsTournament),
+    RATINGS(ArgsRatings),
+    PROFILE(ArgsProfile),
 }
 
 fn main() {
     let app = App::parse();
     let mut cfg = load_app_config();
     let (crypt, db) = load_services();
-    let helper = HelperService::new(crypt, db);
+    let helper = HelperService::new(crypt, db, RngService::new(app.seed));
+    migrate_records(&cfg, &helper);
 
     let result: Result<Output, Failure>;
 
     if let Some(_) = &cfg.user {
         result = match app.command {
             Command::INIT(args) => init::handle(args, &mut cfg, &helper),
-            Command::START(args) => start::handle(args, &mut cfg, &helper)
+            Command::START(args) => start::handle(args, &mut cfg, &helper),
+            Command::ANALYZE(args) => analyze::handle(args, &mut cfg, &helper),
+            Command::PUZZLE(args) => puzzle::handle(args, &mut cfg, &helper),
+            Command::DAILY(args) => daily::handle(args, &mut cfg, &helper),
+            Command::BOOK(args) => book::handle(args, &mut cfg, &helper),
+            Command::SOLVE(args) => solve::handle(args, &mut cfg, &helper),
+            Command::TRAIN(args) => train::handle(args, &mut cfg, &helper),
+            Command::ARENA(args) => arena::handle(args, &mut cfg, &helper),
+            Command::EXPLORE(args) => explore::handle(args, &mut cfg, &helper),
+            Command::TOURNAMENT(args) => tournament::handle(args, &mut cfg, &helper),
+            Command::RATINGS(args) => ratings::handle(args, &mut cfg, &helper),
+            Command::PROFILE(args) => profile::handle(args, &mut cfg, &helper),
         };
     } else {
         result = match app.command {
@@ -43,23 +68,28 @@ fn main() {
         Err(e) => {
             let exit_code: i32 = e.code.as_i32();
             _process_failure(e);
+            println!("Replay this session with --seed {}", helper.rng.seed());
             std::process::exit(exit_code);
         }
     }
 }
 
 fn load_app_config() -> AppConfig {
-    let config = match confy::load(APP_NAME, CONFIG_NAME) {
+    let config: Result<AppConfig, Failure> = match confy::load(APP_NAME, CONFIG_NAME) {
         Ok(g) => Ok(g),
         Err(e) => Err(Failure {
             message: e.to_string(),
-            trace: "".to_string(),
+            // Points at the bad entry, such as an unknown algorithm in a profile
+            trace: std::error::Error::source(&e).map(|g| format!("Reason: {}", g)).unwrap_or_default(),
             code: ResultCode::ConfigUpdateFailed,
         }),
     };
 
     match config {
-        Ok(config) => return config,
+        Ok(mut config) => {
+            config.migrate();
+            return config;
+        }
         Err(e) => {
             let exit_code = e.code.as_i32();
             _process_failure(e);
@@ -68,6 +98,16 @@ fn load_app_config() -> AppConfig {
     }
 }
 
+fn migrate_records(cfg: &AppConfig, helper: &HelperService) {
+    if let Some(owner) = cfg.profiles.first()
+        && let Err(e) = helper.migrate_profile_records(&owner.name)
+    {
+        let exit_code = e.code.as_i32();
+        _process_failure(e);
+        std::process::exit(exit_code);
+    }
+}
+
 fn load_services() -> (CryptService, DBService) {
     let crypt = CryptService::new();
     if let Ok(crypt) = crypt {
@@ -11,6 +11,7 @@ pub enum ResultCode {
     PathError = 66,
     DbError = 68,
     SerializationError = 72,
+    EngineError = 76,
     EnvError = 77,
     CryptoError = 78,
     CancelOperation = 100,
@@ -1,13 +1,31 @@
+use std::{fs, path::PathBuf};
+
 use codes::ResultCode;
 use serde::{Deserialize, Serialize};
 
-use crate::{Failure, APP_NAME, CONFIG_NAME};
+use crate::{
+    commands::start::computer::{Algorithm, Style},
+    services::db::records::DELETED_PLAYER,
+    AppResult, Failure, APP_NAME, CONFIG_NAME,
+};
 
 pub mod codes;
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    /// Defaults for "start" when the matching flags aren't given.
+    pub difficulty: Option<u8>,
+    pub algorithm: Option<Algorithm>,
+    pub style: Option<Style>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AppConfig {
+    /// Name of the active profile.
     pub user: Option<String>,
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
 }
 
 impl AppConfig {
@@ -21,12 +39,122 @@ impl AppConfig {
             }),
         }
     }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|g| g.name == name)
+    }
+
+    pub fn active_profile(&self) -> Option<&Profile> {
+        self.user.as_deref().and_then(|g| self.profile(g))
+    }
+
+    /// Adds an empty profile unless one with the name exists; returns whether it was added.
+    pub fn add_profile(&mut self, name: &str) -> bool {
+        if self.profile(name).is_some() {
+            return false;
+        }
+        self.profiles.push(Profile {
+            name: name.to_string(),
+            ..Profile::default()
+        });
+        true
+    }
+
+    /// Configs written before profiles existed only name the user, who gets a profile here.
+    pub fn migrate(&mut self) {
+        if let Some(user) = self.user.clone() {
+            self.add_profile(&user);
+        }
+    }
+}
+
+/// Names a profile can't take: blank ones, the one deleted profiles leave behind, and the `Computer <difficulty> (<algorithm>)`
+/// names computers are rated under, whose rating and results it would share.
+pub fn check_profile_name(name: &str) -> AppResult<()> {
+    if name.trim().is_empty() {
+        return Err(Failure {
+            message: "Name can not be empty".to_string(),
+            trace: "".to_string(),
+            code: ResultCode::InvalidArgs,
+        });
+    }
+    if name.trim() == DELETED_PLAYER {
+        return Err(Failure {
+            message: format!("\"{}\" stands in for deleted profiles", name.trim()),
+            trace: "".to_string(),
+            code: ResultCode::InvalidArgs,
+        });
+    }
+    if is_computer_name(name) {
+        return Err(Failure {
+            message: format!("\"{}\" is reserved for a computer player", name),
+            trace: "Computers are rated as \"Computer <difficulty> (<algorithm>)\"".to_string(),
+            code: ResultCode::InvalidArgs,
+        });
+    }
+    Ok(())
+}
+
+fn is_computer_name(name: &str) -> bool {
+    let Some(rest) = name.trim().strip_prefix("Computer ") else {
+        return false;
+    };
+    match rest.split_once(' ') {
+        Some((difficulty, algorithm)) => {
+            difficulty.parse::<u8>().is_ok() && algorithm.starts_with('(') && algorithm.ends_with(')')
+        }
+        None => false,
+    }
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             user: None,
+            profiles: vec![],
         }
     }
 }
+
+/// Directory holding the config file, created if missing; app data files live next to it.
+pub fn config_dir() -> AppResult<PathBuf> {
+    let config_file_path =
+        confy::get_configuration_file_path(APP_NAME, CONFIG_NAME).map_err(|e| Failure {
+            message: "Failed to get configuration file path".to_string(),
+            trace: format!("Reason: {}", e),
+            code: ResultCode::PathError,
+        })?;
+
+    let config_dir = config_file_path
+        .parent()
+        .ok_or(Failure {
+            message: "Failed to determine configuration directory".to_string(),
+            trace: "Reason: Parent directory does not exist".to_string(),
+            code: ResultCode::PathError,
+        })?
+        .to_path_buf();
+
+    fs::create_dir_all(&config_dir).map_err(|e| Failure {
+        message: "Failed to create config directory".to_string(),
+        trace: format!("Reason: {}", e),
+        code: ResultCode::PathError,
+    })?;
+
+    Ok(config_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_profile_name;
+
+    #[test]
+    fn test_computer_names_are_reserved() {
+        assert!(check_profile_name("alice").is_ok());
+        assert!(check_profile_name("Computer").is_ok());
+        assert!(check_profile_name("Computer Science").is_ok());
+        assert!(check_profile_name(" ").is_err());
+        assert!(check_profile_name("Deleted player").is_err());
+        assert!(check_profile_name("Computer 2 (Minimax)").is_err());
+        assert!(check_profile_name("Computer 4 (Mcts)").is_err());
+    }
+}
@@ -1,7 +1,8 @@
-use std::fs;
-
-use super::{config::codes::ResultCode, crypt::CryptService};
-use crate::{AppResult, Failure, APP_NAME, CONFIG_NAME};
+use super::{
+    config::{codes::ResultCode, config_dir},
+    crypt::CryptService,
+};
+use crate::{AppResult, Failure};
 use records::{Record, RecordKey};
 use rusqlite::{params, Connection};
 
@@ -16,27 +17,7 @@ pub struct DBService {
 
 impl DBService {
     pub fn new(crypt: Option<CryptService>) -> AppResult<Self> {
-        let config_file_path =
-            confy::get_configuration_file_path(APP_NAME, CONFIG_NAME).map_err(|e| Failure {
-                message: "Failed to get configuration file path".to_string(),
-                trace: format!("Reason: {}", e),
-                code: ResultCode::PathError,
-            })?;
-
-        let config_dir = config_file_path
-            .parent()
-            .ok_or(Failure {
-                message: "Failed to determine configuration directory".to_string(),
-                trace: "Reason: Parent directory does not exist".to_string(),
-                code: ResultCode::PathError,
-            })?
-            .to_path_buf();
-
-        fs::create_dir_all(&config_dir).map_err(|e| Failure {
-            message: "Failed to create config directory".to_string(),
-            trace: format!("Reason: {}", e),
-            code: ResultCode::PathError,
-        })?;
+        let config_dir = config_dir()?;
 
         let db_path = config_dir.join(DB_FILE);
         let conn = Connection::open(db_path).map_err(|e| Failure {
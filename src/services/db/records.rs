@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use strum::IntoEnumIterator;
@@ -33,6 +35,12 @@ impl Record {
 #[derive(Serialize, Deserialize, Debug, Clone, EnumString, EnumIter, AsRefStr)]
 pub enum RecordKey {
     CommandUsage,
+    PuzzleProgress,
+    DailyChallenge,
+    Menace,
+    Tournaments,
+    MatchHistory,
+    Ratings,
     Custom(String),
 }
 
@@ -43,13 +51,29 @@ impl RecordKey {
                 _ => self.as_ref().to_owned().to_string().to_lowercase(),
             }
         }
+
+    /// The record kept apart for each profile, e.g. `puzzleprogress:alice`.
+    pub fn for_profile(&self, profile: &str) -> RecordKey {
+        RecordKey::Custom(format!("{}:{}", self.as_string(), profile))
+    }
 }
 
 // ANALYTICS
 #[derive(EnumString, EnumIter, AsRefStr)]
 pub enum ToolsAnalytics {
     INIT,
-    START
+    START,
+    ANALYZE,
+    PUZZLE,
+    DAILY,
+    BOOK,
+    SOLVE,
+    TRAIN,
+    ARENA,
+    EXPLORE,
+    TOURNAMENT,
+    RATINGS,
+    PROFILE,
 }
 
 impl ToolsAnalytics {
@@ -74,3 +98,341 @@ impl Default for CommandUsageRecord {
         }
     }
 }
+
+// PUZZLES
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PuzzleProgressRecord {
+    pub solved: Vec<String>,
+    pub attempts: i32,
+}
+
+// DAILY CHALLENGE
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DailyAttempt {
+    pub day: i64,
+    pub date: String,
+    pub puzzle: String,
+    pub solved: bool,
+    pub score: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct DailyChallengeRecord {
+    pub attempts: Vec<DailyAttempt>,
+}
+
+impl DailyChallengeRecord {
+    pub fn attempt_on(&self, day: i64) -> Option<&DailyAttempt> {
+        self.attempts.iter().find(|g| g.day == day)
+    }
+
+    /// Consecutive solved days ending on `day`.
+    pub fn streak(&self, day: i64) -> i32 {
+        let mut streak = 0;
+        while self
+            .attempt_on(day - streak as i64)
+            .is_some_and(|g| g.solved)
+        {
+            streak += 1;
+        }
+        streak
+    }
+}
+
+// MENACE
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MenaceRecord {
+    /// Beads per cell (1-9) for each canonical position, keyed by board notation.
+    pub boxes: HashMap<String, Vec<(u16, u32)>>,
+    pub games: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MenaceRecord {
+    /// Counts a game against another opponent; `won` is None for a draw.
+    pub fn tally(&mut self, won: Option<bool>) {
+        self.games += 1;
+        match won {
+            Some(true) => self.wins += 1,
+            Some(false) => self.losses += 1,
+            None => self.draws += 1,
+        }
+    }
+}
+
+// TOURNAMENTS
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum TournamentFormat {
+    RoundRobin,
+    Knockout,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ParticipantKind {
+    Human,
+    Computer { difficulty: u8, algorithm: String },
+    Engine { command: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Participant {
+    pub name: String,
+    pub kind: ParticipantKind,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum GameResult {
+    /// Index of the winning participant.
+    Win(usize),
+    Draw,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TournamentGame {
+    pub round: u32,
+    pub x: usize,
+    pub o: usize,
+    pub result: Option<GameResult>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tournament {
+    pub name: String,
+    pub format: TournamentFormat,
+    /// In seeding order, the first being the top seed.
+    pub participants: Vec<Participant>,
+    /// In playing order; knockout rounds are added as the previous one finishes.
+    pub games: Vec<TournamentGame>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TournamentsRecord {
+    pub tournaments: Vec<Tournament>,
+}
+
+// MATCHES
+/// How a game was decided.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum ResultCause {
+    /// Three in a row, or a full board.
+    #[default]
+    Board,
+    /// The loser ran out of time.
+    Flag,
+    /// The loser failed to make a legal move.
+    Forfeit,
+    Resign,
+    /// Both sides agreed to a draw.
+    Agreement,
+    /// Neither side could complete a line any more.
+    DeadDraw,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MatchGame {
+    /// Index into the match players of whoever played X.
+    pub x: usize,
+    /// Index of the winner, None for a draw.
+    pub winner: Option<usize>,
+    #[serde(default)]
+    pub cause: ResultCause,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MatchRecord {
+    pub date: String,
+    pub players: [String; 2],
+    /// Series length, None for games played one after another until stopping.
+    pub best_of: Option<u32>,
+    pub games: Vec<MatchGame>,
+    /// Random seed of the session, to replay it with `--seed`.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+impl MatchRecord {
+    pub fn wins(&self, player: usize) -> u32 {
+        self.games.iter().filter(|g| g.winner == Some(player)).count() as u32
+    }
+
+    pub fn draws(&self) -> u32 {
+        self.games.iter().filter(|g| g.winner.is_none()).count() as u32
+    }
+
+    /// A series is decided once every game is played or the trailing player can't catch up.
+    pub fn is_decided(&self) -> bool {
+        let Some(best_of) = self.best_of else {
+            return false;
+        };
+        let remaining = best_of.saturating_sub(self.games.len() as u32);
+        self.wins(0).abs_diff(self.wins(1)) > remaining || remaining == 0
+    }
+
+    pub fn winner(&self) -> Option<usize> {
+        match self.wins(0).cmp(&self.wins(1)) {
+            std::cmp::Ordering::Greater => Some(0),
+            std::cmp::Ordering::Less => Some(1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct MatchHistoryRecord {
+    pub matches: Vec<MatchRecord>,
+}
+
+/// Stands in for a deleted profile in the matches it played against other profiles.
+pub const DELETED_PLAYER: &str = "Deleted player";
+
+impl MatchHistoryRecord {
+    /// Forgets a deleted player. Matches against another profile are kept for that profile's
+    /// results, with the player renamed to `DELETED_PLAYER`; the others are dropped.
+    pub fn remove_player(&mut self, name: &str, is_profile: impl Fn(&str) -> bool) {
+        self.matches.retain_mut(|record| {
+            let Some(side) = record.players.iter().position(|g| g == name) else {
+                return true;
+            };
+            if !is_profile(&record.players[1 - side]) {
+                return false;
+            }
+            record.players[side] = DELETED_PLAYER.to_string();
+            true
+        });
+    }
+}
+
+// RATINGS
+pub const INITIAL_RATING: f64 = 1200.0;
+/// Games played before a rating counts as established and moves more slowly.
+pub const PROVISIONAL_GAMES: u32 = 20;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Rating {
+    pub rating: f64,
+    pub computer: bool,
+    pub games: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Rating {
+    fn new(computer: bool) -> Self {
+        Rating {
+            rating: INITIAL_RATING,
+            computer,
+            games: 0,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+        }
+    }
+
+    pub fn is_provisional(&self) -> bool {
+        self.games < PROVISIONAL_GAMES
+    }
+
+    fn k_factor(&self) -> f64 {
+        if self.is_provisional() { 40.0 } else { 20.0 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct RatingsRecord {
+    /// Keyed by profile name, or by the computer's name for a difficulty and algorithm.
+    pub ratings: HashMap<String, Rating>,
+}
+
+/// Chance of `rating` scoring against `opponent` under the Elo model.
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+impl RatingsRecord {
+    /// Rates a game between a player and an opponent, another player or the computer; `score`
+    /// is the player's, 1 for a win, 0.5 for a draw and 0 for a loss. Returns both changes.
+    pub fn rate(&mut self, player: &str, opponent: &str, computer: bool, score: f64) -> (f64, f64) {
+        let ra = self.ratings.get(player).cloned().unwrap_or(Rating::new(false));
+        let rb = self.ratings.get(opponent).cloned().unwrap_or(Rating::new(computer));
+        let expected = expected_score(ra.rating, rb.rating);
+        let change_a = ra.k_factor() * (score - expected);
+        let change_b = rb.k_factor() * (expected - score);
+
+        for (name, mut rating, change, score) in [
+            (player, ra, change_a, score),
+            (opponent, rb, change_b, 1.0 - score),
+        ] {
+            rating.rating += change;
+            rating.games += 1;
+            match score {
+                s if s > 0.5 => rating.wins += 1,
+                s if s < 0.5 => rating.losses += 1,
+                _ => rating.draws += 1,
+            }
+            self.ratings.insert(name.to_string(), rating);
+        }
+        (change_a, change_b)
+    }
+
+    /// Everyone rated, best first.
+    pub fn leaderboard(&self) -> Vec<(&String, &Rating)> {
+        let mut table: Vec<(&String, &Rating)> = self.ratings.iter().collect();
+        table.sort_by(|a, b| b.1.rating.total_cmp(&a.1.rating).then(a.0.cmp(b.0)));
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MatchGame, MatchRecord, ResultCause};
+
+    fn series(best_of: Option<u32>, winners: &[Option<usize>]) -> MatchRecord {
+        MatchRecord {
+            date: "2024-01-01".to_string(),
+            players: ["alice".to_string(), "bob".to_string()],
+            best_of,
+            games: winners
+                .iter()
+                .map(|&winner| MatchGame {
+                    x: 0,
+                    winner,
+                    cause: ResultCause::Board,
+                })
+                .collect(),
+            seed: None,
+        }
+    }
+
+    #[test]
+    fn test_series_decided() {
+        // Two wins clinch a best of 3 early
+        let clinched = series(Some(3), &[Some(0), Some(0)]);
+        assert!(clinched.is_decided());
+        assert_eq!(clinched.winner(), Some(0));
+        assert!(!series(Some(3), &[Some(0), Some(1)]).is_decided());
+        assert!(!series(Some(3), &[Some(1)]).is_decided());
+
+        // Draws don't count for either side, so an all-draw series goes the distance tied
+        assert!(!series(Some(3), &[None, None]).is_decided());
+        let drawn = series(Some(3), &[None, None, None]);
+        assert!(drawn.is_decided());
+        assert_eq!(drawn.winner(), None);
+        let late = series(Some(3), &[None, None, Some(1)]);
+        assert!(late.is_decided());
+        assert_eq!(late.winner(), Some(1));
+
+        // An even series can end level
+        assert!(!series(Some(2), &[Some(0)]).is_decided());
+        let level = series(Some(2), &[Some(0), Some(1)]);
+        assert!(level.is_decided());
+        assert_eq!(level.winner(), None);
+        assert!(!series(Some(4), &[Some(1), Some(1)]).is_decided());
+        assert!(series(Some(4), &[Some(1), Some(1), Some(1)]).is_decided());
+
+        // Games played one after another never decide anything
+        assert!(!series(None, &[Some(0); 5]).is_decided());
+    }
+}
@@ -1,22 +1,73 @@
+use std::time::Duration;
+
 use crate::Failure;
 
 use super::{
     config::codes::ResultCode,
     crypt::CryptService,
     db::{
-        records::{CommandUsageRecord, Record, RecordKey, ToolsAnalytics},
+        records::{
+            CommandUsageRecord, DailyAttempt, DailyChallengeRecord, MatchHistoryRecord,
+            MatchRecord, MenaceRecord, PuzzleProgressRecord, Rating, RatingsRecord, Record, RecordKey, ToolsAnalytics,
+            Tournament, TournamentsRecord,
+        },
         DBService,
     },
+    rng::RngService,
 };
 
+/// Parses durations such as `500ms`, `2s`, `1m` or `1m30s`; a bare number means seconds.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim().to_lowercase();
+    let seconds = |secs: f64| {
+        Duration::try_from_secs_f64(secs.max(0.0))
+            .map_err(|_| format!("duration \"{}\" is out of range", value))
+    };
+    if let Ok(secs) = value.parse::<f64>() {
+        return seconds(secs);
+    }
+
+    let mut total = Duration::ZERO;
+    let mut number = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let amount: f64 = number
+            .parse()
+            .map_err(|_| format!("invalid duration \"{}\"", value))?;
+        number.clear();
+        let part = match c {
+            'm' if chars.peek() == Some(&'s') => {
+                chars.next();
+                seconds(amount / 1000.0)?
+            }
+            'h' => seconds(amount * 3600.0)?,
+            'm' => seconds(amount * 60.0)?,
+            's' => seconds(amount)?,
+            _ => return Err(format!("invalid duration unit '{}' in \"{}\"", c, value)),
+        };
+        total = total
+            .checked_add(part)
+            .ok_or_else(|| format!("duration \"{}\" is out of range", value))?;
+    }
+    if !number.is_empty() {
+        return Err(format!("missing unit after {} in \"{}\"", number, value));
+    }
+    Ok(total)
+}
+
 pub struct HelperService {
     pub crypt: CryptService,
     pub db: DBService,
+    pub rng: RngService,
 }
 
 impl HelperService {
-    pub const fn new(crypt: CryptService, db: DBService) -> Self {
-        HelperService { crypt, db }
+    pub const fn new(crypt: CryptService, db: DBService, rng: RngService) -> Self {
+        HelperService { crypt, db, rng }
     }
 
     pub fn generate_inquire_error(&self, e: inquire::InquireError) -> String {
@@ -116,4 +167,227 @@ impl HelperService {
             })
         }
     }
+
+    pub fn get_puzzle_progress(&self, profile: &str) -> Result<PuzzleProgressRecord, Failure> {
+        match self.db.get_record(&RecordKey::PuzzleProgress.for_profile(profile))? {
+            Some(record) => record.value_as::<PuzzleProgressRecord>(),
+            None => Ok(PuzzleProgressRecord::default()),
+        }
+    }
+
+    pub fn update_puzzle_progress(&self, profile: &str, puzzle_id: &str, solved: bool) -> Result<(), Failure> {
+        let mut progress = self.get_puzzle_progress(profile)?;
+        progress.attempts += 1;
+        if solved && !progress.solved.iter().any(|g| g == puzzle_id) {
+            progress.solved.push(puzzle_id.to_string());
+        }
+
+        self.db.create_or_update_record(&Record::new(
+            RecordKey::PuzzleProgress.for_profile(profile),
+            serde_json::to_value(progress).unwrap(),
+            vec![],
+        ))
+    }
+
+    pub fn reset_puzzle_progress(&self, profile: &str) -> Result<(), Failure> {
+        self.db.delete_record(&RecordKey::PuzzleProgress.for_profile(profile))
+    }
+
+    pub fn get_daily_challenge(&self, profile: &str) -> Result<DailyChallengeRecord, Failure> {
+        match self.db.get_record(&RecordKey::DailyChallenge.for_profile(profile))? {
+            Some(record) => record.value_as::<DailyChallengeRecord>(),
+            None => Ok(DailyChallengeRecord::default()),
+        }
+    }
+
+    pub fn record_daily_attempt(
+        &self,
+        profile: &str,
+        attempt: DailyAttempt,
+    ) -> Result<DailyChallengeRecord, Failure> {
+        let mut record = self.get_daily_challenge(profile)?;
+        record.attempts.retain(|g| g.day != attempt.day);
+        record.attempts.push(attempt);
+
+        self.db.create_or_update_record(&Record::new(
+            RecordKey::DailyChallenge.for_profile(profile),
+            serde_json::to_value(&record).unwrap(),
+            vec![],
+        ))?;
+
+        Ok(record)
+    }
+
+    /// Puzzle progress and daily streaks were one record before profiles; it goes to `owner`,
+    /// the user those installs were set up for.
+    pub fn migrate_profile_records(&self, owner: &str) -> Result<(), Failure> {
+        for key in [RecordKey::PuzzleProgress, RecordKey::DailyChallenge] {
+            let Some(record) = self.db.get_record(&key)? else {
+                continue;
+            };
+            let profile_key = key.for_profile(owner);
+            if !self.db.exists_record(&profile_key)? {
+                self.db
+                    .create_or_update_record(&Record::new(profile_key, record.value, record.tags))?;
+            }
+            self.db.delete_record(&key)?;
+        }
+        Ok(())
+    }
+
+    /// Forgets everything kept about a profile: its rating, puzzle progress, daily streak
+    /// and the matches it played, but for those against the remaining profiles.
+    pub fn delete_profile_records(
+        &self,
+        name: &str,
+        is_profile: impl Fn(&str) -> bool,
+    ) -> Result<(), Failure> {
+        self.delete_rating(name)?;
+        self.reset_puzzle_progress(name)?;
+        self.db.delete_record(&RecordKey::DailyChallenge.for_profile(name))?;
+
+        let mut history = self.get_match_history()?;
+        history.remove_player(name, is_profile);
+        self.db.create_or_update_record(&Record::new(
+            RecordKey::MatchHistory,
+            serde_json::to_value(history).unwrap(),
+            vec![],
+        ))
+    }
+
+    pub fn get_menace(&self) -> Result<MenaceRecord, Failure> {
+        match self.db.get_record(&RecordKey::Menace)? {
+            Some(record) => record.value_as::<MenaceRecord>(),
+            None => Ok(MenaceRecord::default()),
+        }
+    }
+
+    pub fn save_menace(&self, record: &MenaceRecord) -> Result<(), Failure> {
+        self.db.create_or_update_record(&Record::new(
+            RecordKey::Menace,
+            serde_json::to_value(record).unwrap(),
+            vec![],
+        ))
+    }
+
+    pub fn reset_menace(&self) -> Result<(), Failure> {
+        self.db.delete_record(&RecordKey::Menace)
+    }
+
+    pub fn get_tournaments(&self) -> Result<TournamentsRecord, Failure> {
+        match self.db.get_record(&RecordKey::Tournaments)? {
+            Some(record) => record.value_as::<TournamentsRecord>(),
+            None => Ok(TournamentsRecord::default()),
+        }
+    }
+
+    /// Stores the tournament, replacing any with the same name.
+    pub fn save_tournament(&self, tournament: &Tournament) -> Result<(), Failure> {
+        let mut record = self.get_tournaments()?;
+        match record.tournaments.iter_mut().find(|g| g.name == tournament.name) {
+            Some(g) => *g = tournament.clone(),
+            None => record.tournaments.push(tournament.clone()),
+        }
+
+        self.db.create_or_update_record(&Record::new(
+            RecordKey::Tournaments,
+            serde_json::to_value(record).unwrap(),
+            vec![],
+        ))
+    }
+
+    pub fn delete_tournament(&self, name: &str) -> Result<bool, Failure> {
+        let mut record = self.get_tournaments()?;
+        let count = record.tournaments.len();
+        record.tournaments.retain(|g| g.name != name);
+
+        self.db.create_or_update_record(&Record::new(
+            RecordKey::Tournaments,
+            serde_json::to_value(&record).unwrap(),
+            vec![],
+        ))?;
+        Ok(record.tournaments.len() < count)
+    }
+
+    pub fn get_match_history(&self) -> Result<MatchHistoryRecord, Failure> {
+        match self.db.get_record(&RecordKey::MatchHistory)? {
+            Some(record) => record.value_as::<MatchHistoryRecord>(),
+            None => Ok(MatchHistoryRecord::default()),
+        }
+    }
+
+    pub fn record_match(&self, record: &MatchRecord) -> Result<(), Failure> {
+        let mut history = self.get_match_history()?;
+        history.matches.push(record.clone());
+
+        self.db.create_or_update_record(&Record::new(
+            RecordKey::MatchHistory,
+            serde_json::to_value(history).unwrap(),
+            vec![],
+        ))
+    }
+
+    pub fn get_ratings(&self) -> Result<RatingsRecord, Failure> {
+        match self.db.get_record(&RecordKey::Ratings)? {
+            Some(record) => record.value_as::<RatingsRecord>(),
+            None => Ok(RatingsRecord::default()),
+        }
+    }
+
+    /// Updates both ratings after a game, returning each side's new rating and its change.
+    pub fn rate_game(
+        &self,
+        player: &str,
+        opponent: &str,
+        computer: bool,
+        score: f64,
+    ) -> Result<[(Rating, f64); 2], Failure> {
+        let mut record = self.get_ratings()?;
+        let (change, opponent_change) = record.rate(player, opponent, computer, score);
+        let ratings = [
+            (record.ratings[player].clone(), change),
+            (record.ratings[opponent].clone(), opponent_change),
+        ];
+
+        self.db.create_or_update_record(&Record::new(
+            RecordKey::Ratings,
+            serde_json::to_value(record).unwrap(),
+            vec![],
+        ))?;
+        Ok(ratings)
+    }
+
+    pub fn delete_rating(&self, name: &str) -> Result<(), Failure> {
+        let mut record = self.get_ratings()?;
+        if record.ratings.remove(name).is_none() {
+            return Ok(());
+        }
+
+        self.db.create_or_update_record(&Record::new(
+            RecordKey::Ratings,
+            serde_json::to_value(record).unwrap(),
+            vec![],
+        ))
+    }
+
+    pub fn reset_ratings(&self) -> Result<(), Failure> {
+        self.db.delete_record(&RecordKey::Ratings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::parse_duration;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("1m30s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("2"), Ok(Duration::from_secs(2)));
+        assert!(parse_duration("inf").is_err());
+        assert!(parse_duration("1e30").is_err());
+        assert!(parse_duration("99999999999999999999h").is_err());
+    }
 }
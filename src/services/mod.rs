@@ -1,4 +1,5 @@
 pub mod config;
 pub mod crypt;
 pub mod db;
-pub mod helper;
\ No newline at end of file
+pub mod helper;
+pub mod rng;
\ No newline at end of file
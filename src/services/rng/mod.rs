@@ -0,0 +1,29 @@
+use std::cell::RefCell;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// Source of every non-cryptographic random choice, so a `--seed` replays a session exactly.
+/// ChaCha8 keeps the same stream for a seed on every platform and rand release.
+pub struct RngService {
+    seed: u64,
+    rng: RefCell<ChaCha8Rng>,
+}
+
+impl RngService {
+    pub fn new(seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(rand::random::<u64>);
+        RngService {
+            seed,
+            rng: RefCell::new(ChaCha8Rng::seed_from_u64(seed)),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn with<T>(&self, f: impl FnOnce(&mut ChaCha8Rng) -> T) -> T {
+        f(&mut self.rng.borrow_mut())
+    }
+}